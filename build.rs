@@ -8,9 +8,7 @@ fn main() {
             let gtk = std::env::var_os("CARGO_FEATURE_GTK3").is_some();
             let xdg = std::env::var_os("CARGO_FEATURE_XDG_PORTAL").is_some();
 
-            if gtk && xdg {
-                panic!("You can't enable both `gtk3` and `xdg-portal` features at once");
-            } else if !gtk && !xdg {
+            if !gtk && !xdg {
                 panic!("You need to choose at least one backend: `gtk3` or `xdg-portal` features");
             }
 
@@ -1,3 +1,4 @@
+#[allow(clippy::empty_loop)]
 fn main() {
     // Spawn dialog on main thread
     let task = rfd::AsyncFileDialog::new().pick_file();
@@ -1,21 +1,26 @@
 use crate::message_dialog::MessageDialogResult;
-use crate::FileHandle;
+use crate::{Error, FileHandle, FileResponse};
+#[cfg(not(target_arch = "wasm32"))]
+use std::ffi::OsString;
 use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
 
-#[cfg(all(
-    any(
-        target_os = "linux",
-        target_os = "freebsd",
-        target_os = "dragonfly",
-        target_os = "netbsd",
-        target_os = "openbsd"
-    ),
-    not(feature = "gtk3")
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
 ))]
-mod linux;
+pub(crate) mod linux;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod automation;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod custom;
 #[cfg(all(
     any(
         target_os = "linux",
@@ -41,33 +46,84 @@ mod win_cid;
         target_os = "netbsd",
         target_os = "openbsd"
     ),
-    not(feature = "gtk3")
+    feature = "xdg-portal"
 ))]
-mod xdg_desktop_portal;
+pub(crate) mod xdg_desktop_portal;
 
 //
 // Sync
 //
+// The `try_*` variants report backend failures instead of folding them into `None`.
+// Backends that can tell those two apart override them, the rest fall back to the
+// infallible implementation.
+//
 
 /// Dialog used to pick file/files
-pub trait FilePickerDialogImpl {
+pub trait FilePickerDialogImpl: Sized {
     fn pick_file(self) -> Option<PathBuf>;
     fn pick_files(self) -> Option<Vec<PathBuf>>;
+
+    fn try_pick_file(self) -> Result<Option<PathBuf>, Error> {
+        Ok(self.pick_file())
+    }
+    fn try_pick_files(self) -> Result<Option<Vec<PathBuf>>, Error> {
+        Ok(self.pick_files())
+    }
+
+    /// Also returns the selected filter and choices, for backends that can tell.
+    fn try_pick_file_with_response(self) -> Result<Option<FileResponse<PathBuf>>, Error> {
+        Ok(self.try_pick_file()?.map(FileResponse::new))
+    }
+    fn try_pick_files_with_response(self) -> Result<Option<FileResponse<Vec<PathBuf>>>, Error> {
+        Ok(self.try_pick_files()?.map(FileResponse::new))
+    }
 }
 
 /// Dialog used to save file
-pub trait FileSaveDialogImpl {
+pub trait FileSaveDialogImpl: Sized {
     fn save_file(self) -> Option<PathBuf>;
+
+    fn try_save_file(self) -> Result<Option<PathBuf>, Error> {
+        Ok(self.save_file())
+    }
+
+    /// Also returns the selected filter and choices, for backends that can tell.
+    fn try_save_file_with_response(self) -> Result<Option<FileResponse<PathBuf>>, Error> {
+        Ok(self.try_save_file()?.map(FileResponse::new))
+    }
+
+    /// Asks for a folder to save the files named `names` in, for backends without a native
+    /// dialog for that.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn try_save_files(self, names: Vec<String>) -> Result<Option<Vec<PathBuf>>, Error>
+    where
+        Self: FolderPickerDialogImpl,
+    {
+        Ok(self
+            .try_pick_folder()?
+            .map(|dir| paths_in_folder(&dir, &names)))
+    }
 }
 
 /// Dialog used to pick folder
-pub trait FolderPickerDialogImpl {
+pub trait FolderPickerDialogImpl: Sized {
     fn pick_folder(self) -> Option<PathBuf>;
     fn pick_folders(self) -> Option<Vec<PathBuf>>;
+
+    fn try_pick_folder(self) -> Result<Option<PathBuf>, Error> {
+        Ok(self.pick_folder())
+    }
+    fn try_pick_folders(self) -> Result<Option<Vec<PathBuf>>, Error> {
+        Ok(self.pick_folders())
+    }
 }
 
-pub trait MessageDialogImpl {
+pub trait MessageDialogImpl: Sized {
     fn show(self) -> MessageDialogResult;
+
+    fn try_show(self) -> Result<MessageDialogResult, Error> {
+        Ok(self.show())
+    }
 }
 
 //
@@ -80,23 +136,159 @@ pub type DialogFutureType<T> = Pin<Box<dyn Future<Output = T> + Send>>;
 #[cfg(target_arch = "wasm32")]
 pub type DialogFutureType<T> = Pin<Box<dyn Future<Output = T>>>;
 
+/// Return type of async dialogs that also tell the selected filter and choices
+pub type ResponseFuture<T> = DialogFutureType<Result<Option<FileResponse<T>>, Error>>;
+
 /// Dialog used to pick file/files
-pub trait AsyncFilePickerDialogImpl {
+pub trait AsyncFilePickerDialogImpl: Sized {
     fn pick_file_async(self) -> DialogFutureType<Option<FileHandle>>;
     fn pick_files_async(self) -> DialogFutureType<Option<Vec<FileHandle>>>;
+
+    fn try_pick_file_async(self) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        infallible(self.pick_file_async())
+    }
+    fn try_pick_files_async(self) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        infallible(self.pick_files_async())
+    }
+
+    /// Also resolves to the selected filter and choices, for backends that can tell.
+    fn try_pick_file_with_response_async(self) -> ResponseFuture<FileHandle> {
+        response(self.try_pick_file_async())
+    }
+    fn try_pick_files_with_response_async(self) -> ResponseFuture<Vec<FileHandle>> {
+        response(self.try_pick_files_async())
+    }
 }
 
 /// Dialog used to pick folder
-pub trait AsyncFolderPickerDialogImpl {
+pub trait AsyncFolderPickerDialogImpl: Sized {
     fn pick_folder_async(self) -> DialogFutureType<Option<FileHandle>>;
     fn pick_folders_async(self) -> DialogFutureType<Option<Vec<FileHandle>>>;
+
+    fn try_pick_folder_async(self) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        infallible(self.pick_folder_async())
+    }
+    fn try_pick_folders_async(self) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        infallible(self.pick_folders_async())
+    }
 }
 
 /// Dialog used to pick folder
-pub trait AsyncFileSaveDialogImpl {
+pub trait AsyncFileSaveDialogImpl: Sized {
     fn save_file_async(self) -> DialogFutureType<Option<FileHandle>>;
+
+    fn try_save_file_async(self) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        infallible(self.save_file_async())
+    }
+
+    /// Also resolves to the selected filter and choices, for backends that can tell.
+    fn try_save_file_with_response_async(self) -> ResponseFuture<FileHandle> {
+        response(self.try_save_file_async())
+    }
+
+    /// Asks for a folder to save the files named `names` in, for backends without a native
+    /// dialog for that.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn try_save_files_async(
+        self,
+        names: Vec<String>,
+    ) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>>
+    where
+        Self: AsyncFolderPickerDialogImpl,
+    {
+        let future = self.try_pick_folder_async();
+        Box::pin(async move {
+            let paths = future.await?.map(|dir| paths_in_folder(dir.path(), &names));
+            Ok(paths.map(|paths| paths.into_iter().map(FileHandle::wrap).collect()))
+        })
+    }
 }
 
-pub trait AsyncMessageDialogImpl {
+pub trait AsyncMessageDialogImpl: Sized {
     fn show_async(self) -> DialogFutureType<MessageDialogResult>;
+
+    fn try_show_async(self) -> DialogFutureType<Result<MessageDialogResult, Error>> {
+        infallible(self.show_async())
+    }
+}
+
+fn infallible<T: 'static>(future: DialogFutureType<T>) -> DialogFutureType<Result<T, Error>> {
+    Box::pin(async move { Ok(future.await) })
+}
+
+/// Wraps the selection of a backend that can't tell more in a [`FileResponse`].
+pub(crate) fn response<T: 'static>(
+    future: DialogFutureType<Result<Option<T>, Error>>,
+) -> ResponseFuture<T> {
+    Box::pin(async move { Ok(future.await?.map(FileResponse::new)) })
+}
+
+/// Paths for the files named `names` in `dir`. Names that are taken, by an existing file or an
+/// earlier name, are numbered like `photo (2).png` instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn paths_in_folder(dir: &Path, names: &[String]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::with_capacity(names.len());
+
+    for name in names {
+        // Only the file name is used, the files must end up in `dir`.
+        let name = Path::new(name)
+            .file_name()
+            .map_or_else(|| OsString::from("file"), OsString::from);
+        let stem = Path::new(&name).file_stem().unwrap_or(&name).to_owned();
+        let extension = Path::new(&name).extension().map(OsString::from);
+
+        let mut path = dir.join(&name);
+        let mut number = 2;
+        while path.exists() || paths.contains(&path) {
+            let mut numbered = stem.clone();
+            numbered.push(format!(" ({number})"));
+            if let Some(extension) = &extension {
+                numbered.push(".");
+                numbered.push(extension);
+            }
+            path = dir.join(numbered);
+            number += 1;
+        }
+
+        paths.push(path);
+    }
+
+    paths
+}
+
+/// Unwraps the result of a `try_*` call for the infallible API, logging the error.
+pub(crate) fn log_error<T: Default>(what: &str, res: Result<T, Error>) -> T {
+    res.unwrap_or_else(|err| {
+        log::error!("{what} error {err}");
+        T::default()
+    })
+}
+
+/// [`log_error`] for the result of an async `try_*` call.
+pub(crate) fn log_error_async<T: Default + 'static>(
+    what: &'static str,
+    future: DialogFutureType<Result<T, Error>>,
+) -> DialogFutureType<T> {
+    Box::pin(async move { log_error(what, future.await) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_paths_in_folder() {
+        let dir = std::env::temp_dir().join(format!("rfd-save-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.png"), b"").unwrap();
+
+        let names = ["a.png", "b.png", "b.png", "../c", "README"].map(String::from);
+        let paths = paths_in_folder(&dir, &names);
+        assert_eq!(
+            paths,
+            ["a (2).png", "b.png", "b (2).png", "c", "README"].map(|name| dir.join(name))
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,249 @@
+//! Dialogs answered from the file named by `RFD_AUTOMATION`, for end-to-end tests of real
+//! binaries on machines without a display.
+
+use super::custom::DialogBackend;
+use crate::{Error, FileDialog, MessageDialog, MessageDialogResult};
+
+use serde_json::{json, Value};
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+const ANSWERS_VAR: &str = "RFD_AUTOMATION";
+const TRANSCRIPT_VAR: &str = "RFD_AUTOMATION_TRANSCRIPT";
+
+/// The automation backend, if `RFD_AUTOMATION` is set.
+pub(crate) fn backend() -> Option<Arc<dyn DialogBackend>> {
+    static AUTOMATION: OnceLock<Option<Arc<Automation>>> = OnceLock::new();
+
+    AUTOMATION
+        .get_or_init(Automation::from_env)
+        .clone()
+        .map(|automation| automation as Arc<dyn DialogBackend>)
+}
+
+struct Automation {
+    /// The answers left, or why the answer file could not be loaded.
+    answers: Mutex<Result<VecDeque<Value>, String>>,
+    transcript: Option<Mutex<File>>,
+}
+
+impl Automation {
+    fn from_env() -> Option<Arc<Self>> {
+        let path = std::env::var_os(ANSWERS_VAR).filter(|path| !path.is_empty())?;
+        log::debug!("Answering dialogs from {path:?}");
+
+        let answers = std::fs::read_to_string(&path)
+            .map_err(|err| format!("can't read {path:?}: {err}"))
+            .and_then(|script| Self::parse(&script));
+
+        let transcript = std::env::var_os(TRANSCRIPT_VAR)
+            .filter(|path| !path.is_empty())
+            .and_then(|path| match File::create(&path) {
+                Ok(file) => Some(Mutex::new(file)),
+                Err(err) => {
+                    log::error!("Can't create the dialog transcript {path:?}: {err}");
+                    None
+                }
+            });
+
+        Some(Arc::new(Self {
+            answers: Mutex::new(answers),
+            transcript,
+        }))
+    }
+
+    fn parse(script: &str) -> Result<VecDeque<Value>, String> {
+        match serde_json::from_str(script) {
+            Ok(Value::Array(answers)) => Ok(answers.into()),
+            Ok(_) => Err("the answer file must contain a JSON array".into()),
+            Err(err) => Err(format!("invalid answer file: {err}")),
+        }
+    }
+
+    /// Records `request` in the transcript and returns the answer to it.
+    fn answer(&self, dialog: &str, mut request: Value) -> Result<Value, Error> {
+        request["dialog"] = dialog.into();
+
+        if let Some(transcript) = &self.transcript {
+            let mut transcript = transcript.lock().unwrap();
+            if let Err(err) = writeln!(transcript, "{request}") {
+                log::error!("Can't write the dialog transcript: {err}");
+            }
+        }
+
+        let mut answers = self.answers.lock().unwrap();
+        let answers = answers
+            .as_mut()
+            .map_err(|err| Error::Automation(err.clone()))?;
+        let answer = answers
+            .pop_front()
+            .ok_or_else(|| Error::Automation(format!("no answer left for {dialog}")))?;
+
+        match answer.get("dialog").and_then(Value::as_str) {
+            Some(expected) if expected != dialog => Err(Error::Automation(format!(
+                "the next answer is for {expected}, not {dialog}"
+            ))),
+            _ => Ok(answer),
+        }
+    }
+
+    fn answer_paths(&self, dialog: &str, opt: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error> {
+        let filters: Vec<_> = opt
+            .filters()
+            .iter()
+            .map(|filter| {
+                json!({
+                    "name": filter.name(),
+                    "extensions": filter.extensions().collect::<Vec<_>>(),
+                    "globs": filter.globs().collect::<Vec<_>>(),
+                    "mime_types": filter.mime_types().collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let request = json!({
+            "title": opt.title(),
+            "directory": opt.starting_directory().map(|dir| dir.to_string_lossy()),
+            "file_name": opt.file_name().map(|name| name.to_string_lossy()),
+            "filters": filters,
+            "current_filter": opt.current_filter(),
+            "accept_label": opt.accept_label(),
+        });
+
+        let answer = self.answer(dialog, request)?;
+        if is_cancel(&answer) {
+            return Ok(None);
+        }
+
+        let paths = match (answer.get("path"), answer.get("paths")) {
+            (Some(Value::String(path)), None) => Some(vec![PathBuf::from(path)]),
+            (None, Some(Value::Array(paths))) => paths
+                .iter()
+                .map(|path| path.as_str().map(PathBuf::from))
+                .collect(),
+            _ => None,
+        };
+
+        paths.map(Some).ok_or_else(|| {
+            Error::Automation(format!(
+                "{dialog} needs a \"path\", \"paths\" or \"cancel\" answer, got {answer}"
+            ))
+        })
+    }
+
+    fn answer_path(&self, dialog: &str, opt: &FileDialog) -> Result<Option<PathBuf>, Error> {
+        self.answer_paths(dialog, opt)
+            .map(|paths| paths.and_then(|paths| paths.into_iter().next()))
+    }
+}
+
+fn is_cancel(answer: &Value) -> bool {
+    answer.get("cancel").and_then(Value::as_bool) == Some(true)
+}
+
+impl DialogBackend for Automation {
+    fn pick_file(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error> {
+        self.answer_path("pick_file", dialog)
+    }
+
+    fn pick_files(&self, dialog: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error> {
+        self.answer_paths("pick_files", dialog)
+    }
+
+    fn pick_folder(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error> {
+        self.answer_path("pick_folder", dialog)
+    }
+
+    fn pick_folders(&self, dialog: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error> {
+        self.answer_paths("pick_folders", dialog)
+    }
+
+    fn save_file(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error> {
+        self.answer_path("save_file", dialog)
+    }
+
+    fn show_message(&self, dialog: &MessageDialog) -> Result<MessageDialogResult, Error> {
+        let request = json!({
+            "level": format!("{:?}", dialog.level()),
+            "title": dialog.title(),
+            "description": dialog.description(),
+            "buttons": format!("{:?}", dialog.buttons()),
+        });
+
+        let answer = self.answer("message", request)?;
+        if is_cancel(&answer) {
+            return Ok(MessageDialogResult::Cancel);
+        }
+
+        match answer.get("button").and_then(Value::as_str) {
+            Some("Yes") => Ok(MessageDialogResult::Yes),
+            Some("No") => Ok(MessageDialogResult::No),
+            Some("Ok") => Ok(MessageDialogResult::Ok),
+            Some("Cancel") => Ok(MessageDialogResult::Cancel),
+            Some(custom) => Ok(MessageDialogResult::Custom(custom.into())),
+            None => Err(Error::Automation(format!(
+                "message needs a \"button\" or \"cancel\" answer, got {answer}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn automation(script: &str) -> Automation {
+        Automation {
+            answers: Mutex::new(Automation::parse(script)),
+            transcript: None,
+        }
+    }
+
+    #[test]
+    fn answers_in_order() {
+        let automation = automation(
+            r#"[
+                {"dialog": "pick_files", "paths": ["/a", "/b"]},
+                {"path": "/out.txt"},
+                {"cancel": true},
+                {"dialog": "message", "button": "Retry"},
+                {"dialog": "message", "button": "Yes"}
+            ]"#,
+        );
+        let dialog = FileDialog::new();
+
+        assert_eq!(
+            automation.pick_files(&dialog).unwrap(),
+            Some(vec!["/a".into(), "/b".into()])
+        );
+        assert_eq!(
+            automation.save_file(&dialog).unwrap(),
+            Some("/out.txt".into())
+        );
+        assert_eq!(automation.pick_folder(&dialog).unwrap(), None);
+        assert_eq!(
+            automation.show_message(&MessageDialog::new()).unwrap(),
+            MessageDialogResult::Custom("Retry".into())
+        );
+        assert!(matches!(
+            automation.pick_file(&dialog),
+            Err(Error::Automation(_))
+        ));
+        assert!(matches!(
+            automation.pick_file(&dialog),
+            Err(Error::Automation(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_script() {
+        let automation = automation(r#"{"path": "/a"}"#);
+        assert!(matches!(
+            automation.pick_file(&FileDialog::new()),
+            Err(Error::Automation(_))
+        ));
+    }
+}
@@ -0,0 +1,176 @@
+use super::DialogFutureType;
+use crate::{Error, FileDialog, FileHandle, MessageDialog, MessageDialogResult};
+
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+/// A dialog implementation that replaces the native one.
+///
+/// Set it on a single dialog with [`FileDialog::with_backend`] (or the equivalent method on
+/// [`AsyncFileDialog`](crate::AsyncFileDialog), [`MessageDialog`] and
+/// [`AsyncMessageDialog`](crate::AsyncMessageDialog)), or for the whole process with
+/// [`set_backend`]. The dialog passed to each method describes what the application asked for,
+/// use its getters (eg. [`FileDialog::title`]) to build your own UI.
+///
+/// Only the synchronous methods are required. The asynchronous ones default to running the
+/// synchronous method when the future is first polled, override them if your backend can
+/// answer without blocking.
+pub trait DialogBackend: Send + Sync + 'static {
+    fn pick_file(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error>;
+    fn pick_files(&self, dialog: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error>;
+    fn pick_folder(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error>;
+    fn pick_folders(&self, dialog: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error>;
+    fn save_file(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error>;
+    fn show_message(&self, dialog: &MessageDialog) -> Result<MessageDialogResult, Error>;
+
+    fn pick_file_async(
+        self: Arc<Self>,
+        dialog: FileDialog,
+    ) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        Box::pin(async move {
+            self.pick_file(&dialog)
+                .map(|path| path.map(FileHandle::wrap))
+        })
+    }
+
+    fn pick_files_async(
+        self: Arc<Self>,
+        dialog: FileDialog,
+    ) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        Box::pin(async move {
+            self.pick_files(&dialog)
+                .map(|paths| paths.map(|paths| paths.into_iter().map(FileHandle::wrap).collect()))
+        })
+    }
+
+    fn pick_folder_async(
+        self: Arc<Self>,
+        dialog: FileDialog,
+    ) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        Box::pin(async move {
+            self.pick_folder(&dialog)
+                .map(|path| path.map(FileHandle::wrap))
+        })
+    }
+
+    fn pick_folders_async(
+        self: Arc<Self>,
+        dialog: FileDialog,
+    ) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        Box::pin(async move {
+            self.pick_folders(&dialog)
+                .map(|paths| paths.map(|paths| paths.into_iter().map(FileHandle::wrap).collect()))
+        })
+    }
+
+    fn save_file_async(
+        self: Arc<Self>,
+        dialog: FileDialog,
+    ) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        Box::pin(async move {
+            self.save_file(&dialog)
+                .map(|path| path.map(FileHandle::wrap))
+        })
+    }
+
+    fn show_message_async(
+        self: Arc<Self>,
+        dialog: MessageDialog,
+    ) -> DialogFutureType<Result<MessageDialogResult, Error>> {
+        Box::pin(async move { self.show_message(&dialog) })
+    }
+}
+
+static GLOBAL_BACKEND: RwLock<Option<Arc<dyn DialogBackend>>> = RwLock::new(None);
+
+/// Route every dialog of the process through `backend`.
+///
+/// Dialogs that have their own backend set with `with_backend` keep using it.
+pub fn set_backend(backend: Arc<dyn DialogBackend>) {
+    *GLOBAL_BACKEND.write().unwrap() = Some(backend);
+}
+
+/// Go back to the native dialogs after a call to [`set_backend`].
+pub fn clear_backend() {
+    *GLOBAL_BACKEND.write().unwrap() = None;
+}
+
+/// A [`DialogBackend`] stored in a dialog builder.
+#[derive(Clone)]
+pub(crate) struct CustomBackend(pub Arc<dyn DialogBackend>);
+
+impl CustomBackend {
+    /// The backend a dialog should use instead of the native one, if any.
+    ///
+    /// `RFD_AUTOMATION` wins over everything else, it has to answer every dialog of the process.
+    pub fn resolve(local: &Option<CustomBackend>) -> Option<Arc<dyn DialogBackend>> {
+        super::automation::backend()
+            .or_else(|| local.as_ref().map(|backend| backend.0.clone()))
+            .or_else(|| GLOBAL_BACKEND.read().unwrap().clone())
+    }
+}
+
+impl std::fmt::Debug for CustomBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CustomBackend")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+
+    impl DialogBackend for Fixed {
+        fn pick_file(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error> {
+            Ok(dialog.title().map(PathBuf::from))
+        }
+        fn pick_files(&self, _: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error> {
+            Ok(None)
+        }
+        fn pick_folder(&self, _: &FileDialog) -> Result<Option<PathBuf>, Error> {
+            Ok(None)
+        }
+        fn pick_folders(&self, _: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error> {
+            Ok(None)
+        }
+        fn save_file(&self, _: &FileDialog) -> Result<Option<PathBuf>, Error> {
+            Err(Error::NoDisplay)
+        }
+        fn show_message(&self, dialog: &MessageDialog) -> Result<MessageDialogResult, Error> {
+            Ok(MessageDialogResult::Custom(dialog.description().into()))
+        }
+    }
+
+    #[test]
+    fn with_backend() {
+        let backend: Arc<dyn DialogBackend> = Arc::new(Fixed);
+
+        let dialog = FileDialog::new()
+            .set_title("/tmp/a")
+            .with_backend(backend.clone());
+        assert_eq!(dialog.pick_file(), Some(PathBuf::from("/tmp/a")));
+
+        let dialog = FileDialog::new().with_backend(backend.clone());
+        assert!(matches!(
+            dialog.clone().try_save_file(),
+            Err(Error::NoDisplay)
+        ));
+        assert_eq!(dialog.save_file(), None);
+
+        let file = futures::executor::block_on(
+            crate::AsyncFileDialog::new()
+                .set_title("/tmp/b")
+                .with_backend(backend.clone())
+                .pick_file(),
+        );
+        assert_eq!(file.map(PathBuf::from), Some(PathBuf::from("/tmp/b")));
+
+        let res = MessageDialog::new()
+            .set_description("hi")
+            .with_backend(backend)
+            .show();
+        assert_eq!(res, MessageDialogResult::Custom("hi".into()));
+    }
+}
@@ -1,11 +1,14 @@
 mod file_dialog;
 mod message_dialog;
 
+pub(crate) use file_dialog::{file_dialog, file_dialog_async};
+pub(crate) use message_dialog::{message_dialog, message_dialog_async};
+
 mod gtk_future;
 
 mod utils;
 
-pub(self) trait AsGtkDialog {
+trait AsGtkDialog {
     fn gtk_dialog_ptr(&self) -> *mut gtk_sys::GtkDialog;
     unsafe fn show(&self);
 }
@@ -2,182 +2,80 @@ pub mod dialog_ffi;
 
 use dialog_ffi::GtkFileDialog;
 
-use std::path::PathBuf;
-
 use super::utils::GtkGlobalThread;
+use crate::backend::linux::{DialogKind, Picked};
 use crate::backend::DialogFutureType;
-use crate::{FileDialog, FileHandle};
+use crate::file_dialog::Choice;
+use crate::{Error, FileDialog};
 
 use super::gtk_future::GtkDialogFuture;
 
-//
-// File Picker
-//
-
-use crate::backend::FilePickerDialogImpl;
-impl FilePickerDialogImpl for FileDialog {
-    fn pick_file(self) -> Option<PathBuf> {
-        GtkGlobalThread::instance().run_blocking(move || {
-            let dialog = GtkFileDialog::build_pick_file(&self);
-
-            if dialog.run() == gtk_sys::GTK_RESPONSE_ACCEPT {
-                dialog.get_result()
-            } else {
-                None
-            }
-        })
+impl GtkFileDialog {
+    fn build(kind: DialogKind, opt: &FileDialog) -> Self {
+        let dialog = match kind {
+            DialogKind::PickFile => Self::build_pick_file(opt),
+            DialogKind::PickFiles => Self::build_pick_files(opt),
+            DialogKind::PickFolder => Self::build_pick_folder(opt),
+            DialogKind::PickFolders => Self::build_pick_folders(opt),
+            DialogKind::SaveFile => Self::build_save_file(opt),
+        };
+        dialog.set_current_filter(opt.current_filter.as_deref());
+        dialog.add_choices(&opt.choices);
+        dialog
     }
 
-    fn pick_files(self) -> Option<Vec<PathBuf>> {
-        GtkGlobalThread::instance().run_blocking(move || {
-            let dialog = GtkFileDialog::build_pick_files(&self);
-
-            if dialog.run() == gtk_sys::GTK_RESPONSE_ACCEPT {
-                Some(dialog.get_results())
-            } else {
-                None
-            }
-        })
+    fn picked(&self, kind: DialogKind, choices: &[Choice]) -> Picked {
+        let paths = if kind.is_multiple() {
+            self.get_results()
+        } else {
+            self.get_result().into_iter().collect()
+        };
+
+        Picked {
+            paths,
+            filter: self.selected_filter(),
+            choices: self.selected_choices(choices),
+        }
     }
 }
 
-use crate::backend::AsyncFilePickerDialogImpl;
-impl AsyncFilePickerDialogImpl for FileDialog {
-    fn pick_file_async(self) -> DialogFutureType<Option<FileHandle>> {
-        let builder = move || GtkFileDialog::build_pick_file(&self);
-
-        let future = GtkDialogFuture::new(builder, |dialog, res_id| {
-            if res_id == gtk_sys::GTK_RESPONSE_ACCEPT {
-                dialog.get_result().map(FileHandle::wrap)
-            } else {
-                None
-            }
-        });
-
-        Box::pin(future)
+/// Shows a GTK file chooser and blocks until the user answers it.
+pub fn file_dialog(kind: DialogKind, opt: FileDialog) -> Result<Option<Picked>, Error> {
+    let gtk = GtkGlobalThread::instance();
+    if !gtk.is_initialized() {
+        return Err(Error::NoDisplay);
     }
 
-    fn pick_files_async(self) -> DialogFutureType<Option<Vec<FileHandle>>> {
-        let builder = move || GtkFileDialog::build_pick_files(&self);
+    Ok(gtk.run_blocking(move || {
+        let dialog = GtkFileDialog::build(kind, &opt);
 
-        let future = GtkDialogFuture::new(builder, |dialog, res_id| {
-            if res_id == gtk_sys::GTK_RESPONSE_ACCEPT {
-                Some(
-                    dialog
-                        .get_results()
-                        .into_iter()
-                        .map(FileHandle::wrap)
-                        .collect(),
-                )
-            } else {
-                None
-            }
-        });
-
-        Box::pin(future)
-    }
+        if dialog.run() == gtk_sys::GTK_RESPONSE_ACCEPT {
+            Some(dialog.picked(kind, &opt.choices))
+        } else {
+            None
+        }
+    }))
 }
 
-//
-// Folder Picker
-//
-
-use crate::backend::FolderPickerDialogImpl;
-impl FolderPickerDialogImpl for FileDialog {
-    fn pick_folder(self) -> Option<PathBuf> {
-        GtkGlobalThread::instance().run_blocking(move || {
-            let dialog = GtkFileDialog::build_pick_folder(&self);
-
-            if dialog.run() == gtk_sys::GTK_RESPONSE_ACCEPT {
-                dialog.get_result()
-            } else {
-                None
-            }
-        })
+/// Shows a GTK file chooser without blocking.
+pub fn file_dialog_async(
+    kind: DialogKind,
+    opt: FileDialog,
+) -> DialogFutureType<Result<Option<Picked>, Error>> {
+    if !GtkGlobalThread::instance().is_initialized() {
+        return Box::pin(std::future::ready(Err(Error::NoDisplay)));
     }
 
-    fn pick_folders(self) -> Option<Vec<PathBuf>> {
-        GtkGlobalThread::instance().run_blocking(move || {
-            let dialog = GtkFileDialog::build_pick_folders(&self);
+    let choices = opt.choices.clone();
+    let builder = move || GtkFileDialog::build(kind, &opt);
 
-            if dialog.run() == gtk_sys::GTK_RESPONSE_ACCEPT {
-                Some(dialog.get_results())
-            } else {
-                None
-            }
-        })
-    }
-}
+    let future = GtkDialogFuture::new(builder, move |dialog, res_id| {
+        if res_id == gtk_sys::GTK_RESPONSE_ACCEPT {
+            Some(dialog.picked(kind, &choices))
+        } else {
+            None
+        }
+    });
 
-use crate::backend::AsyncFolderPickerDialogImpl;
-impl AsyncFolderPickerDialogImpl for FileDialog {
-    fn pick_folder_async(self) -> DialogFutureType<Option<FileHandle>> {
-        let builder = move || GtkFileDialog::build_pick_folder(&self);
-
-        let future = GtkDialogFuture::new(builder, |dialog, res_id| {
-            if res_id == gtk_sys::GTK_RESPONSE_ACCEPT {
-                dialog.get_result().map(FileHandle::wrap)
-            } else {
-                None
-            }
-        });
-
-        Box::pin(future)
-    }
-
-    fn pick_folders_async(self) -> DialogFutureType<Option<Vec<FileHandle>>> {
-        let builder = move || GtkFileDialog::build_pick_folders(&self);
-
-        let future = GtkDialogFuture::new(builder, |dialog, res_id| {
-            if res_id == gtk_sys::GTK_RESPONSE_ACCEPT {
-                Some(
-                    dialog
-                        .get_results()
-                        .into_iter()
-                        .map(FileHandle::wrap)
-                        .collect(),
-                )
-            } else {
-                None
-            }
-        });
-
-        Box::pin(future)
-    }
-}
-
-//
-// File Save
-//
-
-use crate::backend::FileSaveDialogImpl;
-impl FileSaveDialogImpl for FileDialog {
-    fn save_file(self) -> Option<PathBuf> {
-        GtkGlobalThread::instance().run_blocking(move || {
-            let dialog = GtkFileDialog::build_save_file(&self);
-
-            if dialog.run() == gtk_sys::GTK_RESPONSE_ACCEPT {
-                dialog.get_result()
-            } else {
-                None
-            }
-        })
-    }
-}
-
-use crate::backend::AsyncFileSaveDialogImpl;
-impl AsyncFileSaveDialogImpl for FileDialog {
-    fn save_file_async(self) -> DialogFutureType<Option<FileHandle>> {
-        let builder = move || GtkFileDialog::build_save_file(&self);
-
-        let future = GtkDialogFuture::new(builder, |dialog, res_id| {
-            if res_id == gtk_sys::GTK_RESPONSE_ACCEPT {
-                dialog.get_result().map(FileHandle::wrap)
-            } else {
-                None
-            }
-        });
-
-        Box::pin(future)
-    }
+    Box::pin(async move { Ok(future.await) })
 }
@@ -1,10 +1,12 @@
 use super::super::AsGtkDialog;
+use crate::file_dialog::Choice;
 use crate::FileDialog;
 use gtk_sys::GtkFileChooserNative;
 
 use std::{
-    ffi::{CStr, CString},
-    ops::Deref,
+    collections::HashMap,
+    ffi::{c_char, CStr, CString, OsStr},
+    os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
     ptr,
 };
@@ -22,8 +24,9 @@ pub struct GtkFileDialog {
 }
 
 impl GtkFileDialog {
-    fn new(title: &str, action: GtkFileChooserAction) -> Self {
+    fn new(title: &str, action: GtkFileChooserAction, accept_label: Option<&str>) -> Self {
         let title = CString::new(title).unwrap();
+        let accept_label = accept_label.and_then(|label| CString::new(label).ok());
 
         let ptr = unsafe {
             let dialog = gtk_sys::gtk_file_chooser_native_new(
@@ -31,7 +34,9 @@ impl GtkFileDialog {
                 ptr::null_mut(),
                 action as i32,
                 // passing null for the texts will use the default text, which has full support for i18n
-                std::ptr::null(),
+                accept_label
+                    .as_ref()
+                    .map_or(ptr::null(), |label| label.as_ptr()),
                 std::ptr::null(),
             );
             dialog as _
@@ -47,9 +52,9 @@ impl GtkFileDialog {
                     let filter = gtk_sys::gtk_file_filter_new();
 
                     let paterns: Vec<_> = f
-                        .extensions
-                        .iter()
-                        .filter_map(|e| CString::new(format!("*.{}", e)).ok())
+                        .to_globs(true)
+                        .into_iter()
+                        .filter_map(|p| CString::new(p).ok())
                         .collect();
 
                     gtk_sys::gtk_file_filter_set_name(filter, name.as_ptr());
@@ -58,15 +63,91 @@ impl GtkFileDialog {
                         gtk_sys::gtk_file_filter_add_pattern(filter, p.as_ptr());
                     }
 
+                    for mime in f.mime_types() {
+                        if let Ok(mime) = CString::new(mime) {
+                            gtk_sys::gtk_file_filter_add_mime_type(filter, mime.as_ptr());
+                        }
+                    }
+
                     gtk_sys::gtk_file_chooser_add_filter(self.ptr as _, filter);
                 }
             }
         }
     }
 
-    fn set_file_name(&self, name: Option<&str>) {
+    pub fn add_choices(&self, choices: &[Choice]) {
+        for choice in choices {
+            let (Ok(id), Ok(label), Ok(default)) = (
+                CString::new(choice.id.as_str()),
+                CString::new(choice.label.as_str()),
+                CString::new(choice.default.as_str()),
+            ) else {
+                continue;
+            };
+
+            let options: Vec<_> = choice
+                .options
+                .iter()
+                .filter_map(|(id, label)| {
+                    Some((
+                        CString::new(id.as_str()).ok()?,
+                        CString::new(label.as_str()).ok()?,
+                    ))
+                })
+                .collect();
+
+            // GTK takes NULL terminated arrays, and shows a checkbox when there are none.
+            let mut ids: Vec<*const c_char> = options.iter().map(|(id, _)| id.as_ptr()).collect();
+            let mut labels: Vec<*const c_char> =
+                options.iter().map(|(_, label)| label.as_ptr()).collect();
+            let (ids, labels) = if choice.is_checkbox() {
+                (ptr::null_mut(), ptr::null_mut())
+            } else {
+                ids.push(ptr::null());
+                labels.push(ptr::null());
+                (ids.as_mut_ptr(), labels.as_mut_ptr())
+            };
+
+            unsafe {
+                gtk_sys::gtk_file_chooser_add_choice(
+                    self.ptr as _,
+                    id.as_ptr(),
+                    label.as_ptr(),
+                    ids,
+                    labels,
+                );
+                gtk_sys::gtk_file_chooser_set_choice(self.ptr as _, id.as_ptr(), default.as_ptr());
+            }
+        }
+    }
+
+    /// Selects the filter named `name`, among the ones added with [`GtkFileDialog::add_filters`].
+    pub fn set_current_filter(&self, name: Option<&str>) {
+        let Some(name) = name else {
+            return;
+        };
+
+        unsafe {
+            let filters = gtk_sys::gtk_file_chooser_list_filters(self.ptr as _);
+
+            let mut item = filters;
+            while !item.is_null() {
+                let filter = (*item).data as *mut gtk_sys::GtkFileFilter;
+                let filter_name = gtk_sys::gtk_file_filter_get_name(filter);
+                if !filter_name.is_null() && CStr::from_ptr(filter_name).to_str() == Ok(name) {
+                    gtk_sys::gtk_file_chooser_set_filter(self.ptr as _, filter);
+                    break;
+                }
+                item = (*item).next;
+            }
+
+            glib_sys::g_slist_free(filters);
+        }
+    }
+
+    fn set_file_name(&self, name: Option<&OsStr>) {
         if let Some(name) = name {
-            if let Ok(name) = CString::new(name) {
+            if let Ok(name) = CString::new(name.as_bytes()) {
                 unsafe {
                     gtk_sys::gtk_file_chooser_set_filename(self.ptr as _, name.as_ptr());
                 }
@@ -74,9 +155,10 @@ impl GtkFileDialog {
         }
     }
 
-    fn set_current_name(&self, name: Option<&str>) {
+    /// The name typed in a save dialog is UTF-8 for GTK, unlike the paths.
+    fn set_current_name(&self, name: Option<&OsStr>) {
         if let Some(name) = name {
-            if let Ok(name) = CString::new(name) {
+            if let Ok(name) = CString::new(name.to_string_lossy().as_bytes()) {
                 unsafe {
                     gtk_sys::gtk_file_chooser_set_current_name(self.ptr as _, name.as_ptr());
                 }
@@ -86,29 +168,61 @@ impl GtkFileDialog {
 
     fn set_path(&self, path: Option<&Path>) {
         if let Some(path) = path {
-            if let Some(path) = path.to_str() {
-                if let Ok(path) = CString::new(path) {
-                    unsafe {
-                        gtk_sys::gtk_file_chooser_set_current_folder(self.ptr as _, path.as_ptr());
-                    }
+            if let Ok(path) = CString::new(path.as_os_str().as_bytes()) {
+                unsafe {
+                    gtk_sys::gtk_file_chooser_set_current_folder(self.ptr as _, path.as_ptr());
                 }
             }
         }
     }
 
     pub fn get_result(&self) -> Option<PathBuf> {
-        let cstr = unsafe {
+        unsafe {
             let chosen_filename = gtk_sys::gtk_file_chooser_get_filename(self.ptr as _);
-            CStr::from_ptr(chosen_filename).to_str()
-        };
+            if chosen_filename.is_null() {
+                return None;
+            }
 
-        if let Ok(cstr) = cstr {
-            Some(PathBuf::from(cstr.to_owned()))
-        } else {
-            None
+            let path = OsStr::from_bytes(CStr::from_ptr(chosen_filename).to_bytes()).into();
+            glib_sys::g_free(chosen_filename as _);
+            Some(path)
         }
     }
 
+    /// Name of the selected filter
+    pub fn selected_filter(&self) -> Option<String> {
+        unsafe {
+            let filter = gtk_sys::gtk_file_chooser_get_filter(self.ptr as _);
+            if filter.is_null() {
+                return None;
+            }
+
+            let name = gtk_sys::gtk_file_filter_get_name(filter);
+            if name.is_null() {
+                return None;
+            }
+            CStr::from_ptr(name).to_str().ok().map(String::from)
+        }
+    }
+
+    /// The option selected for each of `choices`, by choice id
+    pub fn selected_choices(&self, choices: &[Choice]) -> HashMap<String, String> {
+        choices
+            .iter()
+            .filter_map(|choice| {
+                let id = CString::new(choice.id.as_str()).ok()?;
+                let value = unsafe {
+                    let value = gtk_sys::gtk_file_chooser_get_choice(self.ptr as _, id.as_ptr());
+                    if value.is_null() {
+                        return None;
+                    }
+                    CStr::from_ptr(value).to_str().ok()?
+                };
+                Some((choice.id.clone(), value.to_owned()))
+            })
+            .collect()
+    }
+
     pub fn get_results(&self) -> Vec<PathBuf> {
         #[derive(Debug)]
         struct FileList(*mut glib_sys::GSList);
@@ -134,14 +248,9 @@ impl GtkFileDialog {
             unsafe { gtk_sys::gtk_file_chooser_get_filenames(self.ptr as *mut _) };
 
         let paths: Vec<PathBuf> = FileList(chosen_filenames)
-            .filter_map(|item| {
-                let cstr = unsafe { CStr::from_ptr(item.data as _).to_str() };
-
-                if let Ok(cstr) = cstr {
-                    Some(PathBuf::from(cstr.to_owned()))
-                } else {
-                    None
-                }
+            .map(|item| {
+                let cstr = unsafe { CStr::from_ptr(item.data as _) };
+                PathBuf::from(OsStr::from_bytes(cstr.to_bytes()))
             })
             .collect();
 
@@ -158,6 +267,7 @@ impl GtkFileDialog {
         let mut dialog = GtkFileDialog::new(
             opt.title.as_deref().unwrap_or("Open File"),
             GtkFileChooserAction::Open,
+            opt.accept_label.as_deref(),
         );
 
         dialog.add_filters(&opt.filters);
@@ -167,7 +277,7 @@ impl GtkFileDialog {
             (opt.starting_directory.to_owned(), opt.file_name.as_deref())
         {
             path.push(file_name);
-            dialog.set_file_name(path.deref().to_str());
+            dialog.set_file_name(Some(path.as_os_str()));
         } else {
             dialog.set_file_name(opt.file_name.as_deref());
         }
@@ -179,6 +289,7 @@ impl GtkFileDialog {
         let mut dialog = GtkFileDialog::new(
             opt.title.as_deref().unwrap_or("Save File"),
             GtkFileChooserAction::Save,
+            opt.accept_label.as_deref(),
         );
 
         unsafe { gtk_sys::gtk_file_chooser_set_do_overwrite_confirmation(dialog.ptr as _, 1) };
@@ -192,7 +303,7 @@ impl GtkFileDialog {
             path.push(file_name);
             if path.exists() {
                 // the user edited an existing document
-                dialog.set_file_name(path.deref().to_str());
+                dialog.set_file_name(Some(path.as_os_str()));
             } else {
                 // the user just created a new document
                 dialog.set_current_name(opt.file_name.as_deref());
@@ -209,6 +320,7 @@ impl GtkFileDialog {
         let dialog = GtkFileDialog::new(
             opt.title.as_deref().unwrap_or("Select Folder"),
             GtkFileChooserAction::SelectFolder,
+            opt.accept_label.as_deref(),
         );
         dialog.set_path(opt.starting_directory.as_deref());
 
@@ -216,7 +328,7 @@ impl GtkFileDialog {
             (opt.starting_directory.to_owned(), opt.file_name.as_deref())
         {
             path.push(file_name);
-            dialog.set_file_name(path.deref().to_str());
+            dialog.set_file_name(Some(path.as_os_str()));
         } else {
             dialog.set_file_name(opt.file_name.as_deref());
         }
@@ -228,6 +340,7 @@ impl GtkFileDialog {
         let dialog = GtkFileDialog::new(
             opt.title.as_deref().unwrap_or("Select Folder"),
             GtkFileChooserAction::SelectFolder,
+            opt.accept_label.as_deref(),
         );
         unsafe { gtk_sys::gtk_file_chooser_set_select_multiple(dialog.ptr as _, 1) };
         dialog.set_path(opt.starting_directory.as_deref());
@@ -236,7 +349,7 @@ impl GtkFileDialog {
             (opt.starting_directory.to_owned(), opt.file_name.as_deref())
         {
             path.push(file_name);
-            dialog.set_file_name(path.deref().to_str());
+            dialog.set_file_name(Some(path.as_os_str()));
         } else {
             dialog.set_file_name(opt.file_name.as_deref());
         }
@@ -248,6 +361,7 @@ impl GtkFileDialog {
         let mut dialog = GtkFileDialog::new(
             opt.title.as_deref().unwrap_or("Open File"),
             GtkFileChooserAction::Open,
+            opt.accept_label.as_deref(),
         );
 
         unsafe { gtk_sys::gtk_file_chooser_set_select_multiple(dialog.ptr as _, 1) };
@@ -258,7 +372,7 @@ impl GtkFileDialog {
             (opt.starting_directory.to_owned(), opt.file_name.as_deref())
         {
             path.push(file_name);
-            dialog.set_file_name(path.deref().to_str());
+            dialog.set_file_name(Some(path.as_os_str()));
         } else {
             dialog.set_file_name(opt.file_name.as_deref());
         }
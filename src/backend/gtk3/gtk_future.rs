@@ -15,7 +15,10 @@ struct FutureState<R, D> {
 
 unsafe impl<R, D> Send for FutureState<R, D> {}
 
-pub(super) struct GtkDialogFuture<R, D> {
+/// A GTK dialog shown on the GTK thread, resolving when the user answers it.
+///
+/// Dropping the future before that destroys the dialog.
+pub(super) struct GtkDialogFuture<R: 'static, D: 'static> {
     state: Arc<Mutex<FutureState<R, D>>>,
 }
 
@@ -69,6 +72,18 @@ impl<R: Default + 'static, D: AsGtkDialog + 'static> GtkDialogFuture<R, D> {
     }
 }
 
+impl<R, D> Drop for GtkDialogFuture<R, D> {
+    fn drop(&mut self) {
+        let state = self.state.clone();
+        // Runs after the dialog was built, idle callbacks are called in order.
+        GtkGlobalThread::instance().run(move || {
+            // Take the dialog out first, destroying it can emit a response.
+            let dialog = state.lock().unwrap().dialog.take();
+            drop(dialog);
+        });
+    }
+}
+
 impl<R, D> std::future::Future for GtkDialogFuture<R, D> {
     type Output = R;
 
@@ -131,8 +146,8 @@ unsafe fn connect_response<F: Fn(GtkResponseType) + 'static>(dialog: *mut GtkDia
     let f: Box<F> = Box::new(f);
     connect_raw(
         dialog as *mut _,
-        b"response\0".as_ptr() as *const _,
-        Some(transmute::<_, unsafe extern "C" fn()>(
+        c"response".as_ptr() as *const _,
+        Some(transmute::<*const (), unsafe extern "C" fn()>(
             response_trampoline::<F> as *const (),
         )),
         Box::into_raw(f),
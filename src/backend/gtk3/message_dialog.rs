@@ -5,8 +5,9 @@ use super::gtk_future::GtkDialogFuture;
 use super::utils::GtkGlobalThread;
 use super::AsGtkDialog;
 
+use crate::backend::DialogFutureType;
 use crate::message_dialog::{MessageButtons, MessageDialog, MessageLevel};
-use crate::MessageDialogResult;
+use crate::{Error, MessageDialogResult};
 
 pub struct GtkMessageDialog {
     buttons: MessageButtons,
@@ -87,7 +88,7 @@ impl GtkMessageDialog {
                 gtk_sys::GTK_DIALOG_MODAL,
                 level,
                 buttons,
-                b"%s\0".as_ptr() as *mut _,
+                c"%s".as_ptr() as *mut _,
                 title.as_ptr(),
             ) as *mut gtk_sys::GtkDialog;
 
@@ -95,14 +96,8 @@ impl GtkMessageDialog {
             // Also set the window title, otherwise it would be empty
             gtk_sys::gtk_window_set_title(dialog as _, title.as_ptr());
 
-            for custom_button in custom_buttons {
-                if let Some((custom_button_cstr, response_id)) = custom_button {
-                    gtk_sys::gtk_dialog_add_button(
-                        dialog,
-                        custom_button_cstr.as_ptr(),
-                        response_id,
-                    );
-                }
+            for (custom_button_cstr, response_id) in custom_buttons.into_iter().flatten() {
+                gtk_sys::gtk_dialog_add_button(dialog, custom_button_cstr.as_ptr(), response_id);
             }
 
             dialog
@@ -120,7 +115,10 @@ impl GtkMessageDialog {
 
     pub fn run(self) -> MessageDialogResult {
         let res = unsafe { gtk_sys::gtk_dialog_run(self.ptr) };
+        self.response_to_result(res)
+    }
 
+    fn response_to_result(&self, res: gtk_sys::GtkResponseType) -> MessageDialogResult {
         use MessageButtons::*;
         match (&self.buttons, res) {
             (Ok | OkCancel, gtk_sys::GTK_RESPONSE_OK) => MessageDialogResult::Ok,
@@ -186,31 +184,30 @@ impl AsGtkDialog for GtkMessageDialog {
     }
 }
 
-use crate::backend::MessageDialogImpl;
-
-impl MessageDialogImpl for MessageDialog {
-    fn show(self) -> MessageDialogResult {
-        GtkGlobalThread::instance().run_blocking(move || {
-            let dialog = GtkMessageDialog::new(self);
-            dialog.run()
-        })
+/// Shows a GTK message dialog and blocks until the user answers it.
+pub fn message_dialog(opt: MessageDialog) -> Result<MessageDialogResult, Error> {
+    let gtk = GtkGlobalThread::instance();
+    if !gtk.is_initialized() {
+        return Err(Error::NoDisplay);
     }
-}
 
-use crate::backend::AsyncMessageDialogImpl;
-use crate::backend::DialogFutureType;
+    Ok(gtk.run_blocking(move || {
+        let dialog = GtkMessageDialog::new(opt);
+        dialog.run()
+    }))
+}
 
-impl AsyncMessageDialogImpl for MessageDialog {
-    fn show_async(self) -> DialogFutureType<MessageDialogResult> {
-        let builder = move || GtkMessageDialog::new(self);
-
-        let future = GtkDialogFuture::new(builder, |_, res| match res {
-            gtk_sys::GTK_RESPONSE_OK => MessageDialogResult::Ok,
-            gtk_sys::GTK_RESPONSE_CANCEL => MessageDialogResult::Cancel,
-            gtk_sys::GTK_RESPONSE_YES => MessageDialogResult::Yes,
-            gtk_sys::GTK_RESPONSE_NO => MessageDialogResult::No,
-            _ => unreachable!(),
-        });
-        Box::pin(future)
+/// Shows a GTK message dialog without blocking.
+pub fn message_dialog_async(
+    opt: MessageDialog,
+) -> DialogFutureType<Result<MessageDialogResult, Error>> {
+    if !GtkGlobalThread::instance().is_initialized() {
+        return Box::pin(std::future::ready(Err(Error::NoDisplay)));
     }
+
+    let builder = move || GtkMessageDialog::new(opt);
+
+    let future = GtkDialogFuture::new(builder, |dialog, res| dialog.response_to_result(res));
+
+    Box::pin(async move { Ok(future.await) })
 }
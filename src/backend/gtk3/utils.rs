@@ -1,7 +1,7 @@
 use std::ptr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::thread::spawn;
 
 static GTK_THREAD: OnceLock<GtkGlobalThread> = OnceLock::new();
@@ -12,12 +12,13 @@ static GTK_THREAD: OnceLock<GtkGlobalThread> = OnceLock::new();
 /// initialize GTK.
 pub struct GtkGlobalThread {
     running: Arc<AtomicBool>,
+    initialized: bool,
 }
 
 impl GtkGlobalThread {
     /// Return the global, lazily-initialized instance of the global GTK thread.
     pub(super) fn instance() -> &'static Self {
-        GTK_THREAD.get_or_init(|| Self::new())
+        GTK_THREAD.get_or_init(Self::new)
     }
 
     fn new() -> Self {
@@ -25,10 +26,12 @@ impl GtkGlobalThread {
         // gtk_main_iteration unblocks and we exit the thread on the next iteration.
         let running = Arc::new(AtomicBool::new(true));
         let thread_running = Arc::clone(&running);
+        let (init_tx, init_rx) = mpsc::channel();
 
         spawn(move || {
             let initialized =
                 unsafe { gtk_sys::gtk_init_check(ptr::null_mut(), ptr::null_mut()) == 1 };
+            let _ = init_tx.send(initialized);
             if !initialized {
                 return;
             }
@@ -46,9 +49,17 @@ impl GtkGlobalThread {
 
         Self {
             running: Arc::new(AtomicBool::new(true)),
+            initialized: init_rx.recv().unwrap_or(false),
         }
     }
 
+    /// Whether GTK could be initialized, it can't when there is no display to connect to.
+    ///
+    /// Nothing ever runs on the GTK thread if this is `false`.
+    pub(super) fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
     /// Run a function on the GTK thread, blocking on the result which is then passed back.
     pub(super) fn run_blocking<
         T: Send + Clone + std::fmt::Debug + 'static,
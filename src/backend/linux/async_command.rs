@@ -1,17 +1,23 @@
 use std::{
-    io,
+    io::{self, Read},
     pin::Pin,
+    process::{Child, Output, Stdio},
     sync::{Arc, Mutex},
     task::{Context, Poll, Waker},
 };
 
 struct State {
     waker: Option<Waker>,
-    data: Option<io::Result<std::process::Output>>,
+    data: Option<io::Result<Output>>,
+    exited: bool,
 }
 
+/// A child process awaited from a background thread.
+///
+/// Dropping the future before the process exited kills it.
 pub struct AsyncCommand {
     state: Arc<Mutex<State>>,
+    child: Option<Arc<Mutex<Child>>>,
 }
 
 impl AsyncCommand {
@@ -19,15 +25,32 @@ impl AsyncCommand {
         let state = Arc::new(Mutex::new(State {
             waker: None,
             data: None,
+            exited: false,
         }));
 
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+
+        let child = match child {
+            Ok(child) => Arc::new(Mutex::new(child)),
+            Err(err) => {
+                state.lock().unwrap().data = Some(Err(err));
+                return Self { state, child: None };
+            }
+        };
+
         std::thread::spawn({
             let state = state.clone();
+            let child = child.clone();
             move || {
-                let output = command.output();
+                let output = wait_with_output(&child);
 
                 let mut state = state.lock().unwrap();
                 state.data = Some(output);
+                state.exited = true;
 
                 if let Some(waker) = state.waker.take() {
                     waker.wake();
@@ -35,12 +58,58 @@ impl AsyncCommand {
             }
         });
 
-        Self { state }
+        Self {
+            state,
+            child: Some(child),
+        }
+    }
+}
+
+/// Like [`Child::wait_with_output`], without keeping the child locked while it runs,
+/// so that it can be killed in the meantime.
+fn wait_with_output(child: &Mutex<Child>) -> io::Result<Output> {
+    let (stdout, stderr) = {
+        let mut child = child.lock().unwrap();
+        (child.stdout.take(), child.stderr.take())
+    };
+
+    // Read stderr on its own thread, the child could block on a full pipe otherwise.
+    let stderr = std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut stderr) = stderr {
+            stderr.read_to_end(&mut buffer)?;
+        }
+        io::Result::Ok(buffer)
+    });
+
+    let mut stdout_buffer = Vec::new();
+    if let Some(mut stdout) = stdout {
+        stdout.read_to_end(&mut stdout_buffer)?;
+    }
+    let stderr = stderr.join().unwrap()?;
+
+    let status = child.lock().unwrap().wait()?;
+
+    Ok(Output {
+        status,
+        stdout: stdout_buffer,
+        stderr,
+    })
+}
+
+impl Drop for AsyncCommand {
+    fn drop(&mut self) {
+        let exited = self.state.lock().unwrap().exited;
+
+        if let (Some(child), false) = (&self.child, exited) {
+            // The waiting thread reaps the child once it is gone.
+            let _ = child.lock().unwrap().kill();
+        }
     }
 }
 
 impl std::future::Future for AsyncCommand {
-    type Output = io::Result<std::process::Output>;
+    type Output = io::Result<Output>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut state = self.state.lock().unwrap();
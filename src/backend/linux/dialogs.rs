@@ -0,0 +1,521 @@
+//! Dialog traits for Linux & BSDs, dispatching every dialog to the backend picked at runtime.
+
+use std::path::PathBuf;
+
+use pollster::block_on;
+
+#[cfg(feature = "gtk3")]
+use super::select::is_backend_failure;
+use super::select::{file_backends, message_backends, with_fallback, Selected};
+use super::{kdialog, terminal, zenity, DialogKind, Picked};
+#[cfg(feature = "gtk3")]
+use crate::backend::gtk3;
+#[cfg(feature = "xdg-portal")]
+use crate::backend::xdg_desktop_portal;
+use crate::backend::{
+    log_error, log_error_async, paths_in_folder, DialogFutureType, ResponseFuture,
+};
+use crate::message_dialog::MessageDialog;
+use crate::{
+    DialogHandle, Error, FileDialog, FileHandle, FileResponse, MessageButtons, MessageDialogResult,
+};
+
+async fn zenity_paths(
+    kind: DialogKind,
+    dialog: &FileDialog,
+) -> Result<Option<Vec<PathBuf>>, Error> {
+    match kind {
+        DialogKind::PickFile => zenity::pick_file(dialog)
+            .await
+            .map(|path| path.map(|p| vec![p])),
+        DialogKind::PickFiles => zenity::pick_files(dialog).await,
+        DialogKind::PickFolder => zenity::pick_folder(dialog)
+            .await
+            .map(|path| path.map(|p| vec![p])),
+        DialogKind::PickFolders => zenity::pick_folders(dialog).await,
+        DialogKind::SaveFile => zenity::save_file(dialog)
+            .await
+            .map(|path| path.map(|p| vec![p])),
+    }
+}
+
+async fn kdialog_paths(
+    kind: DialogKind,
+    dialog: &FileDialog,
+) -> Result<Option<Vec<PathBuf>>, Error> {
+    match kind {
+        DialogKind::PickFile => kdialog::pick_file(dialog)
+            .await
+            .map(|path| path.map(|p| vec![p])),
+        DialogKind::PickFiles => kdialog::pick_files(dialog).await,
+        DialogKind::PickFolder => kdialog::pick_folder(dialog)
+            .await
+            .map(|path| path.map(|p| vec![p])),
+        DialogKind::PickFolders => kdialog::pick_folders(dialog).await,
+        DialogKind::SaveFile => kdialog::save_file(dialog)
+            .await
+            .map(|path| path.map(|p| vec![p])),
+    }
+}
+
+/// Shows a file dialog with `helper`: zenity, kdialog or the terminal.
+async fn helper_file_dialog(
+    helper: Selected,
+    kind: DialogKind,
+    dialog: &FileDialog,
+) -> Result<Option<Picked>, Error> {
+    let paths = match helper {
+        Selected::KDialog => kdialog_paths(kind, dialog).await,
+        Selected::Terminal => terminal::file_dialog(kind, dialog).await,
+        _ => zenity_paths(kind, dialog).await,
+    }?;
+
+    Ok(paths.map(|paths| {
+        // The helpers don't tell the selected filter, guess it from the file name. They select
+        // the current filter by default, so that is the best guess when none matches.
+        let current = dialog
+            .current_filter
+            .as_ref()
+            .and_then(|name| dialog.filters.iter().find(|filter| &filter.name == name));
+        let filter = paths
+            .first()
+            .and_then(|path| dialog.filters.iter().find(|filter| filter.matches(path)))
+            .or(current)
+            .or(dialog.filters.first())
+            .filter(|_| kind == DialogKind::SaveFile)
+            .map(|filter| filter.name.clone());
+
+        Picked {
+            paths,
+            filter,
+            ..Default::default()
+        }
+    }))
+}
+
+/// Shows a file dialog with `backend`.
+async fn show_file_dialog(
+    backend: Selected,
+    kind: DialogKind,
+    dialog: &FileDialog,
+) -> Result<Option<Picked>, Error> {
+    match backend {
+        #[cfg(feature = "gtk3")]
+        Selected::Gtk3 => gtk3::file_dialog_async(kind, dialog.clone()).await,
+        #[cfg(feature = "xdg-portal")]
+        Selected::Portal => xdg_desktop_portal::file_dialog(kind, dialog).await,
+        helper @ (Selected::Zenity | Selected::KDialog | Selected::Terminal) => {
+            helper_file_dialog(helper, kind, dialog).await
+        }
+    }
+}
+
+async fn run_file_dialog(
+    backends: Vec<Selected>,
+    kind: DialogKind,
+    dialog: FileDialog,
+) -> Result<Option<Picked>, Error> {
+    with_fallback(backends, |backend| show_file_dialog(backend, kind, &dialog)).await
+}
+
+/// Shows a dialog with the blocking GTK dialog, when it is the first of `backends`. It can't be
+/// dismissed, so timeouts go through the async one.
+///
+/// `None` if it was not used, or if it failed and the next backend should be tried.
+#[cfg(feature = "gtk3")]
+fn gtk_blocking<T>(
+    backends: &mut Vec<Selected>,
+    timeout: Option<std::time::Duration>,
+    show: impl FnOnce() -> Result<T, Error>,
+) -> Option<Result<T, Error>> {
+    if backends[0] != Selected::Gtk3 || timeout.is_some() {
+        return None;
+    }
+
+    match show() {
+        Err(err) if is_backend_failure(&err) && backends.len() > 1 => {
+            log::debug!(
+                "The Gtk3 backend failed ({err}), falling back to {:?}",
+                backends[1]
+            );
+            backends.remove(0);
+            None
+        }
+        res => Some(res),
+    }
+}
+
+fn file_dialog(kind: DialogKind, dialog: FileDialog) -> Result<Option<Picked>, Error> {
+    #[allow(unused_mut)]
+    let mut backends = block_on(file_backends(kind))?;
+
+    #[cfg(feature = "gtk3")]
+    if let Some(res) = gtk_blocking(&mut backends, dialog.timeout, || {
+        gtk3::file_dialog(kind, dialog.clone())
+    }) {
+        return res;
+    }
+
+    let timeout = dialog.timeout;
+    let future = Box::pin(run_file_dialog(backends, kind, dialog));
+    block_on(DialogHandle::with_timeout(timeout, future))
+}
+
+fn file_dialog_async(
+    kind: DialogKind,
+    dialog: FileDialog,
+) -> DialogFutureType<Result<Option<Picked>, Error>> {
+    Box::pin(async move {
+        let backends = file_backends(kind).await?;
+        run_file_dialog(backends, kind, dialog).await
+    })
+}
+
+/// Asks `backend` for a folder to save the files named `names` in, through the portal
+/// `SaveFiles` method, otherwise with a folder picker.
+async fn show_save_files(
+    backend: Selected,
+    dialog: &FileDialog,
+    names: &[String],
+) -> Result<Option<Vec<PathBuf>>, Error> {
+    match backend {
+        #[cfg(feature = "xdg-portal")]
+        Selected::Portal => Ok(xdg_desktop_portal::save_files(dialog, names)
+            .await?
+            .map(|picked| picked.paths)),
+        backend => {
+            let folder = show_file_dialog(backend, DialogKind::PickFolder, dialog).await?;
+            Ok(in_folder(folder, names))
+        }
+    }
+}
+
+async fn run_save_files(
+    backends: Vec<Selected>,
+    dialog: FileDialog,
+    names: Vec<String>,
+) -> Result<Option<Vec<PathBuf>>, Error> {
+    with_fallback(backends, |backend| {
+        show_save_files(backend, &dialog, &names)
+    })
+    .await
+}
+
+fn in_folder(folder: Option<Picked>, names: &[String]) -> Option<Vec<PathBuf>> {
+    let dir = folder?.paths.into_iter().next()?;
+    Some(paths_in_folder(&dir, names))
+}
+
+/// Shows a file dialog, see [`Picked::response`].
+fn response_dialog(
+    kind: DialogKind,
+    dialog: FileDialog,
+) -> Result<Option<FileResponse<Vec<PathBuf>>>, Error> {
+    let filters = dialog.filters.clone();
+    let auto_extension = kind == DialogKind::SaveFile && dialog.auto_extension;
+    let picked = file_dialog(kind, dialog)?;
+    Ok(picked.and_then(|picked| picked.response(&filters, auto_extension)))
+}
+
+fn response_dialog_async(kind: DialogKind, dialog: FileDialog) -> ResponseFuture<Vec<FileHandle>> {
+    let filters = dialog.filters.clone();
+    let auto_extension = kind == DialogKind::SaveFile && dialog.auto_extension;
+    let future = file_dialog_async(kind, dialog);
+    Box::pin(async move {
+        let response = future
+            .await?
+            .and_then(|picked| picked.response(&filters, auto_extension));
+        Ok(response.map(|res| res.map(|paths| paths.into_iter().map(FileHandle::wrap).collect())))
+    })
+}
+
+fn first<T>(
+    res: Result<Option<FileResponse<Vec<T>>>, Error>,
+) -> Result<Option<FileResponse<T>>, Error> {
+    res.map(|res| {
+        let FileResponse {
+            selection,
+            selected_filter,
+            choices,
+        } = res?;
+        Some(FileResponse {
+            selection: selection.into_iter().next()?,
+            selected_filter,
+            choices,
+        })
+    })
+}
+
+fn first_async<T: 'static>(future: ResponseFuture<Vec<T>>) -> ResponseFuture<T> {
+    Box::pin(async move { first(future.await) })
+}
+
+fn selection<T>(res: Result<Option<FileResponse<T>>, Error>) -> Result<Option<T>, Error> {
+    res.map(|res| res.map(|res| res.selection))
+}
+
+fn selection_async<T: 'static>(
+    future: ResponseFuture<T>,
+) -> DialogFutureType<Result<Option<T>, Error>> {
+    Box::pin(async move { selection(future.await) })
+}
+
+//
+// File Picker
+//
+
+use crate::backend::FilePickerDialogImpl;
+impl FilePickerDialogImpl for FileDialog {
+    fn pick_file(self) -> Option<PathBuf> {
+        log_error("pick_file", self.try_pick_file())
+    }
+
+    fn pick_files(self) -> Option<Vec<PathBuf>> {
+        log_error("pick_files", self.try_pick_files())
+    }
+
+    fn try_pick_file(self) -> Result<Option<PathBuf>, Error> {
+        selection(self.try_pick_file_with_response())
+    }
+
+    fn try_pick_files(self) -> Result<Option<Vec<PathBuf>>, Error> {
+        selection(self.try_pick_files_with_response())
+    }
+
+    fn try_pick_file_with_response(self) -> Result<Option<FileResponse<PathBuf>>, Error> {
+        first(response_dialog(DialogKind::PickFile, self))
+    }
+
+    fn try_pick_files_with_response(self) -> Result<Option<FileResponse<Vec<PathBuf>>>, Error> {
+        response_dialog(DialogKind::PickFiles, self)
+    }
+}
+
+use crate::backend::AsyncFilePickerDialogImpl;
+impl AsyncFilePickerDialogImpl for FileDialog {
+    fn pick_file_async(self) -> DialogFutureType<Option<FileHandle>> {
+        log_error_async("pick_file", self.try_pick_file_async())
+    }
+
+    fn pick_files_async(self) -> DialogFutureType<Option<Vec<FileHandle>>> {
+        log_error_async("pick_files", self.try_pick_files_async())
+    }
+
+    fn try_pick_file_async(self) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        selection_async(self.try_pick_file_with_response_async())
+    }
+
+    fn try_pick_files_async(self) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        selection_async(self.try_pick_files_with_response_async())
+    }
+
+    fn try_pick_file_with_response_async(self) -> ResponseFuture<FileHandle> {
+        first_async(response_dialog_async(DialogKind::PickFile, self))
+    }
+
+    fn try_pick_files_with_response_async(self) -> ResponseFuture<Vec<FileHandle>> {
+        response_dialog_async(DialogKind::PickFiles, self)
+    }
+}
+
+//
+// Folder Picker
+//
+
+use crate::backend::FolderPickerDialogImpl;
+impl FolderPickerDialogImpl for FileDialog {
+    fn pick_folder(self) -> Option<PathBuf> {
+        log_error("pick_folder", self.try_pick_folder())
+    }
+
+    fn pick_folders(self) -> Option<Vec<PathBuf>> {
+        log_error("pick_folders", self.try_pick_folders())
+    }
+
+    fn try_pick_folder(self) -> Result<Option<PathBuf>, Error> {
+        selection(first(response_dialog(DialogKind::PickFolder, self)))
+    }
+
+    fn try_pick_folders(self) -> Result<Option<Vec<PathBuf>>, Error> {
+        selection(response_dialog(DialogKind::PickFolders, self))
+    }
+}
+
+use crate::backend::AsyncFolderPickerDialogImpl;
+impl AsyncFolderPickerDialogImpl for FileDialog {
+    fn pick_folder_async(self) -> DialogFutureType<Option<FileHandle>> {
+        log_error_async("pick_folder", self.try_pick_folder_async())
+    }
+
+    fn pick_folders_async(self) -> DialogFutureType<Option<Vec<FileHandle>>> {
+        log_error_async("pick_folders", self.try_pick_folders_async())
+    }
+
+    fn try_pick_folder_async(self) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        selection_async(first_async(response_dialog_async(
+            DialogKind::PickFolder,
+            self,
+        )))
+    }
+
+    fn try_pick_folders_async(self) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        selection_async(response_dialog_async(DialogKind::PickFolders, self))
+    }
+}
+
+//
+// File Save
+//
+
+use crate::backend::FileSaveDialogImpl;
+impl FileSaveDialogImpl for FileDialog {
+    fn save_file(self) -> Option<PathBuf> {
+        log_error("save_file", self.try_save_file())
+    }
+
+    fn try_save_file(self) -> Result<Option<PathBuf>, Error> {
+        selection(self.try_save_file_with_response())
+    }
+
+    fn try_save_file_with_response(self) -> Result<Option<FileResponse<PathBuf>>, Error> {
+        first(response_dialog(DialogKind::SaveFile, self))
+    }
+
+    fn try_save_files(self, names: Vec<String>) -> Result<Option<Vec<PathBuf>>, Error> {
+        // Only the portal has a dialog of its own, the others pick a folder.
+        #[cfg(feature = "xdg-portal")]
+        {
+            let backends = block_on(file_backends(DialogKind::PickFolder))?;
+            if backends[0] == Selected::Portal {
+                let timeout = self.timeout;
+                let future = Box::pin(run_save_files(backends, self, names));
+                return block_on(DialogHandle::with_timeout(timeout, future));
+            }
+        }
+
+        Ok(in_folder(
+            file_dialog(DialogKind::PickFolder, self)?,
+            &names,
+        ))
+    }
+}
+
+use crate::backend::AsyncFileSaveDialogImpl;
+impl AsyncFileSaveDialogImpl for FileDialog {
+    fn save_file_async(self) -> DialogFutureType<Option<FileHandle>> {
+        log_error_async("save_file", self.try_save_file_async())
+    }
+
+    fn try_save_file_async(self) -> DialogFutureType<Result<Option<FileHandle>, Error>> {
+        selection_async(self.try_save_file_with_response_async())
+    }
+
+    fn try_save_file_with_response_async(self) -> ResponseFuture<FileHandle> {
+        first_async(response_dialog_async(DialogKind::SaveFile, self))
+    }
+
+    fn try_save_files_async(
+        self,
+        names: Vec<String>,
+    ) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        Box::pin(async move {
+            let backends = file_backends(DialogKind::PickFolder).await?;
+            let paths = run_save_files(backends, self, names).await?;
+            Ok(paths.map(|paths| paths.into_iter().map(FileHandle::wrap).collect()))
+        })
+    }
+}
+
+//
+// Message Dialog
+//
+
+async fn zenity_message_dialog(dialog: MessageDialog) -> Result<MessageDialogResult, Error> {
+    match &dialog.buttons {
+        MessageButtons::Ok | MessageButtons::OkCustom(_) => {
+            zenity::message(
+                &dialog.level,
+                &dialog.buttons,
+                &dialog.title,
+                &dialog.description,
+                dialog.timeout,
+            )
+            .await
+        }
+        MessageButtons::OkCancel
+        | MessageButtons::YesNo
+        | MessageButtons::OkCancelCustom(..)
+        | MessageButtons::YesNoCancel
+        | MessageButtons::YesNoCancelCustom(..) => {
+            zenity::question(
+                &dialog.buttons,
+                &dialog.title,
+                &dialog.description,
+                dialog.timeout,
+            )
+            .await
+        }
+    }
+}
+
+/// Shows a message dialog with `backend`.
+async fn show_message(
+    backend: Selected,
+    dialog: MessageDialog,
+) -> Result<MessageDialogResult, Error> {
+    match backend {
+        #[cfg(feature = "gtk3")]
+        Selected::Gtk3 => gtk3::message_dialog_async(dialog).await,
+        Selected::KDialog => kdialog::message(dialog).await,
+        Selected::Terminal => terminal::message(dialog).await,
+        _ => zenity_message_dialog(dialog).await,
+    }
+}
+
+fn run_message(
+    backends: Vec<Selected>,
+    dialog: MessageDialog,
+) -> DialogFutureType<Result<MessageDialogResult, Error>> {
+    Box::pin(with_fallback(backends, move |backend| {
+        show_message(backend, dialog.clone())
+    }))
+}
+
+use crate::backend::MessageDialogImpl;
+impl MessageDialogImpl for MessageDialog {
+    fn show(self) -> MessageDialogResult {
+        log_error("show", self.try_show())
+    }
+
+    fn try_show(self) -> Result<MessageDialogResult, Error> {
+        #[allow(unused_mut)]
+        let mut backends = block_on(message_backends())?;
+
+        #[cfg(feature = "gtk3")]
+        if let Some(res) = gtk_blocking(&mut backends, self.timeout, || {
+            gtk3::message_dialog(self.clone())
+        }) {
+            return res;
+        }
+
+        let timeout = self.timeout;
+        block_on(DialogHandle::with_timeout(
+            timeout,
+            run_message(backends, self),
+        ))
+    }
+}
+
+use crate::backend::AsyncMessageDialogImpl;
+impl AsyncMessageDialogImpl for MessageDialog {
+    fn show_async(self) -> DialogFutureType<MessageDialogResult> {
+        log_error_async("show", self.try_show_async())
+    }
+
+    fn try_show_async(self) -> DialogFutureType<Result<MessageDialogResult, Error>> {
+        Box::pin(async move {
+            let backends = message_backends().await?;
+            run_message(backends, self).await
+        })
+    }
+}
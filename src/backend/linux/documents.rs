@@ -0,0 +1,98 @@
+//! Paths of the document portal, which is how sandboxed (eg. Flatpak) applications are given
+//! access to the files picked in a dialog.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Where the document portal is mounted, `$XDG_RUNTIME_DIR/doc`.
+///
+/// Flatpak mounts it at `/run/user/<uid>/doc` in the sandbox, which is also where
+/// `XDG_RUNTIME_DIR` points to.
+pub(crate) fn mount_point() -> PathBuf {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("/run/user/{}", unsafe { libc::getuid() })));
+    runtime_dir.join("doc")
+}
+
+/// The ID of the document `path` belongs to, if it is in the document portal.
+pub(crate) fn document_id(path: &Path) -> Option<String> {
+    split(path).map(|(id, _)| id)
+}
+
+/// The ID of the document `path` belongs to, and the rest of `path` below the file or folder of
+/// the document, empty when `path` is the document itself.
+pub(crate) fn split(path: &Path) -> Option<(String, PathBuf)> {
+    split_in(&mount_point(), path)
+}
+
+/// The path of `rest`, as returned by [`split`], in the document whose path outside of the
+/// sandbox is `document`.
+#[cfg_attr(not(feature = "xdg-portal"), allow(dead_code))]
+pub(crate) fn host_path(document: PathBuf, rest: &Path) -> PathBuf {
+    // Joining an empty path appends a `/`, which is not a valid path for a file document.
+    if rest.as_os_str().is_empty() {
+        document
+    } else {
+        document.join(rest)
+    }
+}
+
+/// Document portal paths are `<mount point>/<document ID>/<name>`, or
+/// `<mount point>/by-app/<app ID>/<document ID>/<name>`.
+fn split_in(mount_point: &Path, path: &Path) -> Option<(String, PathBuf)> {
+    let mut components = path.strip_prefix(mount_point).ok()?.components();
+
+    let mut id = components.next()?;
+    if id == Component::Normal("by-app".as_ref()) {
+        components.next()?;
+        id = components.next()?;
+    }
+
+    // The ID alone is a folder of the portal, not a document.
+    components.next()?;
+
+    match id {
+        Component::Normal(id) => Some((id.to_str()?.to_owned(), components.collect())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{host_path, split_in};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn document_ids() {
+        let mount = Path::new("/run/user/1000/doc");
+        let id = |path: &str| split_in(mount, Path::new(path)).map(|(id, _)| id);
+
+        assert_eq!(
+            id("/run/user/1000/doc/3a4b5c6d/notes.txt").as_deref(),
+            Some("3a4b5c6d")
+        );
+        assert_eq!(
+            id("/run/user/1000/doc/by-app/org.example.App/3a4b5c6d/notes.txt").as_deref(),
+            Some("3a4b5c6d")
+        );
+        assert_eq!(id("/run/user/1000/doc/3a4b5c6d"), None);
+        assert_eq!(id("/run/user/1000/notes.txt"), None);
+        assert_eq!(id("/home/user/notes.txt"), None);
+
+        let (_, rest) = split_in(mount, Path::new("/run/user/1000/doc/3a4b/src/lib/a.rs")).unwrap();
+        assert_eq!(rest, Path::new("lib/a.rs"));
+        assert_eq!(
+            host_path(PathBuf::from("/home/user/src"), &rest).as_os_str(),
+            "/home/user/src/lib/a.rs"
+        );
+
+        // `Path` comparisons ignore trailing slashes, the strings don't.
+        let (_, rest) = split_in(mount, Path::new("/run/user/1000/doc/3a4b/notes.txt")).unwrap();
+        assert_eq!(rest.as_os_str(), "");
+        assert_eq!(
+            host_path(PathBuf::from("/home/user/notes.txt"), &rest).as_os_str(),
+            "/home/user/notes.txt"
+        );
+    }
+}
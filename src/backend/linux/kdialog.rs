@@ -0,0 +1,278 @@
+//! Dialogs shown with the `kdialog` helper binary, for KDE sessions without a portal.
+
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+    process::Command,
+};
+
+use raw_window_handle::RawWindowHandle;
+
+use super::{has_display, mime};
+use crate::{
+    file_dialog::Filter,
+    message_dialog::{MessageButtons, MessageDialog, MessageLevel},
+    Error, FileDialog, MessageDialogResult,
+};
+
+pub type KDialogResult<T> = Result<T, Error>;
+
+/// How kdialog was answered.
+enum Answer {
+    /// The dialog was accepted (or "Yes" was pressed), with what kdialog printed.
+    Accepted(Vec<u8>),
+    /// The dialog was cancelled (or "No" was pressed).
+    Rejected,
+    /// "Cancel" was pressed in a yes/no/cancel dialog.
+    Cancelled,
+}
+
+fn command(title: Option<&str>, parent: Option<RawWindowHandle>) -> Command {
+    let mut command = Command::new("kdialog");
+
+    if let Some(title) = title {
+        command.args(["--title", title]);
+    }
+
+    // kdialog can only be attached to X11 windows.
+    let xid = match parent {
+        Some(RawWindowHandle::Xlib(handle)) => Some(handle.window.to_string()),
+        Some(RawWindowHandle::Xcb(handle)) => Some(handle.window.get().to_string()),
+        _ => None,
+    };
+    if let Some(xid) = xid {
+        command.args(["--attach", &xid]);
+    }
+
+    command
+}
+
+/// Filters in the KDE syntax, one `patterns|name` line per filter. The first one is selected,
+/// so the current one goes first.
+fn filters(filters: &[Filter], current: Option<&str>) -> Option<String> {
+    let (current, others): (Vec<_>, Vec<_>) = filters
+        .iter()
+        .partition(|filter| Some(filter.name.as_str()) == current);
+
+    let lines: Vec<_> = current
+        .into_iter()
+        .chain(others)
+        .map(|f| {
+            let mut patterns = f.to_globs(true);
+            patterns.extend(f.mime_types().flat_map(mime::patterns));
+            // `|` and new lines are separators, they can't appear in the name.
+            format!(
+                "{}|{}",
+                patterns.join(" "),
+                f.name.replace(['|', '\n'], " ")
+            )
+        })
+        .collect();
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// The starting directory and file name as the single path kdialog takes.
+fn start_path(dialog: &FileDialog) -> OsString {
+    match (&dialog.starting_directory, &dialog.file_name) {
+        (Some(dir), Some(name)) => dir.join(name).into_os_string(),
+        (Some(dir), None) => dir.clone().into_os_string(),
+        (None, Some(name)) => name.clone(),
+        (None, None) => ".".into(),
+    }
+}
+
+/// A file dialog `command`, with the options of `dialog`.
+fn file_command(dialog: &FileDialog, args: &[&str], with_filters: bool) -> Command {
+    let mut command = command(dialog.title.as_deref(), dialog.parent);
+    command.args(args);
+    command.arg(start_path(dialog));
+
+    if with_filters {
+        if let Some(filters) = filters(&dialog.filters, dialog.current_filter.as_deref()) {
+            command.arg(filters);
+        }
+    }
+
+    command
+}
+
+async fn run(command: Command) -> KDialogResult<Answer> {
+    if !has_display() {
+        return Err(Error::NoDisplay);
+    }
+
+    let res = super::async_command::AsyncCommand::spawn(command)
+        .await
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => Error::HelperNotFound("kdialog".into()),
+            _ => Error::Io(err),
+        })?;
+
+    // kdialog exits with 1 when the dialog is cancelled or "No" was pressed, and with 2 for
+    // "Cancel" in yes/no/cancel dialogs. Anything else but 0 means it could not show the dialog.
+    match res.status.code() {
+        Some(0) => Ok(Answer::Accepted(res.stdout)),
+        Some(1) => Ok(Answer::Rejected),
+        Some(2) => Ok(Answer::Cancelled),
+        code => Err(Error::HelperFailed {
+            program: "kdialog".into(),
+            code,
+            stderr: String::from_utf8_lossy(&res.stderr).trim().to_owned(),
+        }),
+    }
+}
+
+/// The paths kdialog printed, one per line, as bytes since they need not be UTF-8.
+async fn run_paths(command: Command) -> KDialogResult<Option<Vec<PathBuf>>> {
+    match run(command).await? {
+        Answer::Accepted(output) => {
+            let paths: Vec<_> = output
+                .split(|&byte| byte == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| PathBuf::from(OsStr::from_bytes(line)))
+                .collect();
+            Ok((!paths.is_empty()).then_some(paths))
+        }
+        Answer::Rejected | Answer::Cancelled => Ok(None),
+    }
+}
+
+async fn run_path(command: Command) -> KDialogResult<Option<PathBuf>> {
+    run_paths(command)
+        .await
+        .map(|paths| paths.and_then(|paths| paths.into_iter().next()))
+}
+
+pub async fn pick_file(dialog: &FileDialog) -> KDialogResult<Option<PathBuf>> {
+    run_path(file_command(dialog, &["--getopenfilename"], true)).await
+}
+
+pub async fn pick_files(dialog: &FileDialog) -> KDialogResult<Option<Vec<PathBuf>>> {
+    let args = ["--multiple", "--separate-output", "--getopenfilename"];
+    run_paths(file_command(dialog, &args, true)).await
+}
+
+pub async fn pick_folder(dialog: &FileDialog) -> KDialogResult<Option<PathBuf>> {
+    run_path(file_command(dialog, &["--getexistingdirectory"], false)).await
+}
+
+/// kdialog can't select multiple folders, this picks a single one.
+pub async fn pick_folders(dialog: &FileDialog) -> KDialogResult<Option<Vec<PathBuf>>> {
+    log::warn!("kdialog can only select a single folder");
+    pick_folder(dialog)
+        .await
+        .map(|path| path.map(|path| vec![path]))
+}
+
+pub async fn save_file(dialog: &FileDialog) -> KDialogResult<Option<PathBuf>> {
+    run_path(file_command(dialog, &["--getsavefilename"], true)).await
+}
+
+pub async fn message(dialog: MessageDialog) -> KDialogResult<MessageDialogResult> {
+    let warning = !matches!(dialog.level, MessageLevel::Info);
+    let title = (!dialog.title.is_empty()).then_some(dialog.title.as_str());
+    let mut command = command(title, dialog.parent);
+
+    let kind = match (&dialog.buttons, &dialog.level) {
+        (MessageButtons::Ok | MessageButtons::OkCustom(_), MessageLevel::Info) => "--msgbox",
+        (MessageButtons::Ok | MessageButtons::OkCustom(_), MessageLevel::Warning) => "--sorry",
+        (MessageButtons::Ok | MessageButtons::OkCustom(_), MessageLevel::Error) => "--error",
+        (MessageButtons::YesNoCancel | MessageButtons::YesNoCancelCustom(..), _) if warning => {
+            "--warningyesnocancel"
+        }
+        (MessageButtons::YesNoCancel | MessageButtons::YesNoCancelCustom(..), _) => "--yesnocancel",
+        _ if warning => "--warningyesno",
+        _ => "--yesno",
+    };
+    command.args([kind, dialog.description.as_str()]);
+
+    match &dialog.buttons {
+        MessageButtons::OkCustom(ok) => {
+            command.args(["--ok-label", ok]);
+        }
+        MessageButtons::OkCancel => {
+            command.args(["--yes-label", "Ok", "--no-label", "Cancel"]);
+        }
+        MessageButtons::OkCancelCustom(ok, cancel) => {
+            command.args(["--yes-label", ok, "--no-label", cancel]);
+        }
+        MessageButtons::YesNoCancelCustom(yes, no, cancel) => {
+            command.args(["--yes-label", yes, "--no-label", no]);
+            command.args(["--cancel-label", cancel]);
+        }
+        _ => {}
+    }
+
+    let answer = run(command).await?;
+
+    Ok(match (&dialog.buttons, answer) {
+        (MessageButtons::Ok, Answer::Accepted(_)) => MessageDialogResult::Ok,
+        (MessageButtons::OkCustom(ok), Answer::Accepted(_)) => {
+            MessageDialogResult::Custom(ok.clone())
+        }
+        (MessageButtons::OkCancel, Answer::Accepted(_)) => MessageDialogResult::Ok,
+        (MessageButtons::OkCancelCustom(ok, _), Answer::Accepted(_)) => {
+            MessageDialogResult::Custom(ok.clone())
+        }
+        (MessageButtons::OkCancelCustom(_, cancel), _) => {
+            MessageDialogResult::Custom(cancel.clone())
+        }
+        (MessageButtons::YesNo | MessageButtons::YesNoCancel, Answer::Accepted(_)) => {
+            MessageDialogResult::Yes
+        }
+        (MessageButtons::YesNo | MessageButtons::YesNoCancel, Answer::Rejected) => {
+            MessageDialogResult::No
+        }
+        (MessageButtons::YesNoCancelCustom(yes, ..), Answer::Accepted(_)) => {
+            MessageDialogResult::Custom(yes.clone())
+        }
+        (MessageButtons::YesNoCancelCustom(_, no, _), Answer::Rejected) => {
+            MessageDialogResult::Custom(no.clone())
+        }
+        (MessageButtons::YesNoCancelCustom(.., cancel), Answer::Cancelled) => {
+            MessageDialogResult::Custom(cancel.clone())
+        }
+        _ => MessageDialogResult::Cancel,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FileDialog;
+
+    #[test]
+    fn file_command_options() {
+        let dialog = FileDialog::default()
+            .set_title("Export")
+            .set_directory("/tmp")
+            .set_file_name("a.png")
+            .add_filter("Text", &["txt"])
+            .add_filter("Image | Photo", &["png"])
+            .set_current_filter("Image | Photo");
+
+        let command = super::file_command(&dialog, &["--getsavefilename"], true);
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "--title",
+                "Export",
+                "--getsavefilename",
+                "/tmp/a.png",
+                "*.[pP][nN][gG]|Image   Photo\n*.[tT][xX][tT]|Text",
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn pick_files() {
+        let paths = pollster::block_on(super::pick_files(&FileDialog::default())).unwrap();
+        for path in paths.unwrap_or_default() {
+            assert!(path.is_file(), "{path:?} is not a file");
+        }
+    }
+}
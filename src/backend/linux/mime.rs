@@ -0,0 +1,382 @@
+//! The shared MIME-info database: file name patterns for the backends that only understand
+//! patterns, and the MIME types of files.
+
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The `mime` folders of the XDG data directories, most important first.
+fn mime_dirs() -> impl Iterator<Item = PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".local/share")));
+    let data_dirs = std::env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+
+    data_home
+        .into_iter()
+        .chain(std::env::split_paths(&data_dirs).collect::<Vec<_>>())
+        .map(|dir| dir.join("mime"))
+}
+
+/// A `weight:type:pattern[:flags]` line of a `globs2` file.
+struct Glob {
+    weight: u32,
+    mime_type: String,
+    pattern: String,
+}
+
+/// Every glob of the database, sorted by decreasing weight.
+fn all_globs() -> &'static [Glob] {
+    static GLOBS: OnceLock<Vec<Glob>> = OnceLock::new();
+
+    GLOBS.get_or_init(|| {
+        let mut globs: Vec<_> = mime_dirs()
+            .filter_map(|dir| std::fs::read_to_string(dir.join("globs2")).ok())
+            .flat_map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .filter_map(|line| {
+                        let mut fields = line.split(':');
+                        Some(Glob {
+                            weight: fields.next()?.parse().unwrap_or(50),
+                            mime_type: fields.next()?.to_owned(),
+                            pattern: fields.next()?.to_owned(),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        globs.sort_by_key(|glob| std::cmp::Reverse(glob.weight));
+        globs
+    })
+}
+
+/// The MIME types whose patterns match the name of `path` best, several when they tie.
+fn glob_matches(path: &Path) -> Vec<&'static str> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let mut best = (0, 0);
+    let mut matches = Vec::new();
+    for glob in all_globs() {
+        let Some(suffix) = glob.pattern.strip_prefix('*') else {
+            continue;
+        };
+        if suffix.contains(['*', '?', '[']) || !name.ends_with(&suffix.to_lowercase()) {
+            continue;
+        }
+
+        // Prefer higher weights, then longer patterns, like `*.tar.gz` over `*.gz`.
+        let key = (glob.weight, suffix.len());
+        if key > best {
+            best = key;
+            matches.clear();
+        }
+        if key == best && !matches.contains(&glob.mime_type.as_str()) {
+            matches.push(glob.mime_type.as_str());
+        }
+    }
+    matches
+}
+
+/// The MIME type of `path`, guessed from its name.
+pub fn mime_type(path: &Path) -> String {
+    if path.is_dir() {
+        return "inode/directory".to_owned();
+    }
+
+    glob_matches(path)
+        .first()
+        .copied()
+        .unwrap_or("application/octet-stream")
+        .to_owned()
+}
+
+/// The MIME type of the file at `path`, from its name and its content, `None` if neither is
+/// known to the database.
+///
+/// Like GLib, a name that matches the patterns of a single type is trusted, otherwise the
+/// content decides.
+pub fn sniff(path: &Path) -> io::Result<Option<String>> {
+    if path.is_dir() {
+        return Ok(Some("inode/directory".to_owned()));
+    }
+
+    let candidates = glob_matches(path);
+    if let [mime_type] = candidates[..] {
+        return Ok(Some(mime_type.to_owned()));
+    }
+
+    let mut content = Vec::new();
+    std::fs::File::open(path)?
+        .take(magic_extent() as u64)
+        .read_to_end(&mut content)?;
+    let sniffed = magic()
+        .iter()
+        .find(|magic| magic.matches(&content))
+        .map(|magic| magic.mime_type.as_str());
+
+    let mime_type = match (sniffed, candidates.first()) {
+        (Some(sniffed), _) if candidates.is_empty() || candidates.contains(&sniffed) => sniffed,
+        (_, Some(candidate)) => *candidate,
+        _ => return Ok(None),
+    };
+    Ok(Some(mime_type.to_owned()))
+}
+
+/// A `[indent]>offset=value[&mask][~word size][+range]` line of a `magic` file.
+struct MagicMatch {
+    indent: usize,
+    offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    range: usize,
+}
+
+impl MagicMatch {
+    fn matches(&self, content: &[u8]) -> bool {
+        (self.offset..self.offset + self.range).any(|start| {
+            let Some(bytes) = content.get(start..start + self.value.len()) else {
+                return false;
+            };
+            match &self.mask {
+                Some(mask) => bytes
+                    .iter()
+                    .zip(&self.value)
+                    .zip(mask)
+                    .all(|((byte, value), mask)| byte & mask == value & mask),
+                None => bytes == self.value,
+            }
+        })
+    }
+
+    /// How much of a file this needs to look at.
+    fn extent(&self) -> usize {
+        self.offset + self.range + self.value.len()
+    }
+}
+
+/// A `[priority:type]` section of a `magic` file.
+struct Magic {
+    priority: u32,
+    mime_type: String,
+    matches: Vec<MagicMatch>,
+}
+
+impl Magic {
+    fn matches(&self, content: &[u8]) -> bool {
+        any_matches(&self.matches, content)
+    }
+}
+
+/// Whether one of the first level of `matches` matches `content`, along with one of its
+/// sub-matches when it has some. Sub-matches follow their parent with a greater indent.
+fn any_matches(matches: &[MagicMatch], content: &[u8]) -> bool {
+    let mut rest = matches;
+    while let Some((first, others)) = rest.split_first() {
+        let children = others
+            .iter()
+            .position(|other| other.indent <= first.indent)
+            .unwrap_or(others.len());
+        let (children, next) = others.split_at(children);
+
+        if first.matches(content) && (children.is_empty() || any_matches(children, content)) {
+            return true;
+        }
+        rest = next;
+    }
+    false
+}
+
+/// Every magic section of the database, sorted by decreasing priority.
+fn magic() -> &'static [Magic] {
+    static MAGIC: OnceLock<Vec<Magic>> = OnceLock::new();
+
+    MAGIC.get_or_init(|| {
+        let mut magic: Vec<_> = mime_dirs()
+            .filter_map(|dir| std::fs::read(dir.join("magic")).ok())
+            .flat_map(|content| parse_magic(&content))
+            .collect();
+
+        magic.sort_by_key(|magic| std::cmp::Reverse(magic.priority));
+        magic
+    })
+}
+
+/// How much of a file the magic of the database needs to look at.
+fn magic_extent() -> usize {
+    static EXTENT: OnceLock<usize> = OnceLock::new();
+
+    *EXTENT.get_or_init(|| {
+        magic()
+            .iter()
+            .flat_map(|magic| &magic.matches)
+            .map(MagicMatch::extent)
+            .max()
+            .unwrap_or(0)
+    })
+}
+
+/// The sections of a `magic` file, as far as it could be parsed.
+fn parse_magic(content: &[u8]) -> Vec<Magic> {
+    let mut magic = Vec::new();
+    let Some(mut content) = content.strip_prefix(b"MIME-Magic\0\n") else {
+        return magic;
+    };
+
+    while let Some(rest) = content.strip_prefix(b"[") {
+        let Some(end) = rest.iter().position(|&byte| byte == b'\n') else {
+            break;
+        };
+        let header = String::from_utf8_lossy(&rest[..end]);
+        let Some((priority, mime_type)) = header
+            .strip_suffix(']')
+            .and_then(|header| header.split_once(':'))
+        else {
+            break;
+        };
+
+        let mut section = Magic {
+            priority: priority.parse().unwrap_or(50),
+            mime_type: mime_type.to_owned(),
+            matches: Vec::new(),
+        };
+        content = &rest[end + 1..];
+        while !content.is_empty() && !content.starts_with(b"[") {
+            let Some((line, rest)) = parse_magic_match(content) else {
+                magic.push(section);
+                return magic;
+            };
+            section.matches.push(line);
+            content = rest;
+        }
+        magic.push(section);
+    }
+
+    magic
+}
+
+/// Parses the match at the start of `content`, and returns it with the rest of `content`.
+fn parse_magic_match(content: &[u8]) -> Option<(MagicMatch, &[u8])> {
+    let (indent, content) = parse_number(content);
+    let content = content.strip_prefix(b">")?;
+    let (offset, content) = parse_number(content);
+    let content = content.strip_prefix(b"=")?;
+
+    let len = u16::from_be_bytes([*content.first()?, *content.get(1)?]) as usize;
+    let mut value = content.get(2..2 + len)?.to_vec();
+    let mut content = &content[2 + len..];
+
+    let mut mask = None;
+    if let Some(rest) = content.strip_prefix(b"&") {
+        mask = Some(rest.get(..len)?.to_vec());
+        content = &rest[len..];
+    }
+    let mut word_size = 1;
+    if let Some(rest) = content.strip_prefix(b"~") {
+        let (size, rest) = parse_number(rest);
+        word_size = size?;
+        content = rest;
+    }
+    let mut range = 1;
+    if let Some(rest) = content.strip_prefix(b"+") {
+        let (length, rest) = parse_number(rest);
+        range = length?;
+        content = rest;
+    }
+
+    // Extensions this does not know about go up to the end of the line.
+    let end = content.iter().position(|&byte| byte == b'\n')?;
+
+    // Words are stored in big endian.
+    if cfg!(target_endian = "little") && word_size > 1 {
+        for bytes in std::iter::once(&mut value).chain(mask.as_mut()) {
+            bytes.chunks_mut(word_size).for_each(|word| word.reverse());
+        }
+    }
+
+    let line = MagicMatch {
+        indent: indent.unwrap_or(0),
+        offset: offset?,
+        value,
+        mask,
+        range,
+    };
+    Some((line, &content[end + 1..]))
+}
+
+/// The decimal number at the start of `content`, if any, and the rest of `content`.
+fn parse_number(content: &[u8]) -> (Option<usize>, &[u8]) {
+    let digits = content
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    let number = std::str::from_utf8(&content[..digits])
+        .ok()
+        .and_then(|digits| digits.parse().ok());
+    (number, &content[digits..])
+}
+
+/// The file name patterns of `mime_type`, which can be a whole media type like `image/*`, with
+/// duplicates.
+pub fn globs(mime_type: &str) -> impl Iterator<Item = &'static str> + '_ {
+    let matches = move |candidate: &str| match mime_type.strip_suffix("/*") {
+        Some(media_type) => candidate
+            .split_once('/')
+            .is_some_and(|(candidate, _)| candidate == media_type),
+        None => candidate == mime_type,
+    };
+
+    all_globs()
+        .iter()
+        .filter(move |glob| matches(&glob.mime_type))
+        .map(|glob| glob.pattern.as_str())
+}
+
+/// The file name patterns of `mime_type`, which can be a whole media type like `image/*`.
+pub fn patterns(mime_type: &str) -> Vec<String> {
+    let mut patterns: Vec<String> = Vec::new();
+    for pattern in globs(mime_type) {
+        if !patterns.iter().any(|known| known == pattern) {
+            patterns.push(pattern.to_owned());
+        }
+    }
+
+    if patterns.is_empty() {
+        log::warn!("No file name pattern is known for {mime_type}");
+    }
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_matches() {
+        let mut content =
+            b"MIME-Magic\0\n[80:image/x-test]\n>0=\0\x04TEST\n1>8=\0\x02ab&\xff\xdf+2\n".to_vec();
+        content.extend(b"[50:text/x-test]\n>0=\0\x02#!\n");
+        let magic = parse_magic(&content);
+
+        assert_eq!(magic.len(), 2);
+        assert_eq!(
+            (magic[0].priority, magic[0].mime_type.as_str()),
+            (80, "image/x-test")
+        );
+        assert_eq!(magic[0].matches[1].indent, 1);
+
+        // The sub-match is case insensitive for its second byte, and looks at offsets 8 and 9.
+        assert!(magic[0].matches(b"TEST____aB"));
+        assert!(magic[0].matches(b"TEST_____ab"));
+        assert!(!magic[0].matches(b"TEST____xx"));
+        assert!(!magic[0].matches(b"TEST"));
+        assert!(magic[1].matches(b"#!/bin/sh"));
+    }
+}
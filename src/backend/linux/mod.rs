@@ -1,2 +1,84 @@
 mod async_command;
+mod dialogs;
+pub(crate) mod documents;
+pub(crate) mod kdialog;
+pub(crate) mod mime;
+mod select;
+mod terminal;
 pub(crate) mod zenity;
+
+pub use select::{set_fallback_policy, set_preferred_backend, FallbackPolicy, LinuxBackend};
+
+use crate::file_dialog::Filter;
+use crate::FileResponse;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Whether there is a display for the helper binaries to show their dialogs on.
+pub(crate) fn has_display() -> bool {
+    std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// The kind of file dialog to show, shared by every Linux backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DialogKind {
+    PickFile,
+    PickFiles,
+    PickFolder,
+    PickFolders,
+    SaveFile,
+}
+
+impl DialogKind {
+    pub fn is_multiple(self) -> bool {
+        matches!(self, Self::PickFiles | Self::PickFolders)
+    }
+
+    #[cfg_attr(not(feature = "xdg-portal"), allow(dead_code))]
+    pub fn is_folder(self) -> bool {
+        matches!(self, Self::PickFolder | Self::PickFolders)
+    }
+}
+
+/// The answer to a file dialog, shared by every Linux backend.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Picked {
+    pub paths: Vec<PathBuf>,
+    /// Name of the filter selected when the user answered, if the backend tells.
+    pub filter: Option<String>,
+    /// The option selected for each choice the backend showed, by choice id.
+    pub choices: HashMap<String, String>,
+}
+
+impl Picked {
+    /// The answer, with the selected filter looked up in `filters`. `None` if nothing was picked.
+    ///
+    /// With `auto_extension`, the first extension of that filter is appended to the first path,
+    /// unless it already ends with one of them.
+    pub fn response(
+        self,
+        filters: &[Filter],
+        auto_extension: bool,
+    ) -> Option<FileResponse<Vec<PathBuf>>> {
+        if self.paths.is_empty() {
+            return None;
+        }
+
+        let mut response = FileResponse::new(self.paths);
+        response.selected_filter = self
+            .filter
+            .and_then(|name| filters.iter().find(|filter| filter.name == name))
+            .cloned();
+        response.choices = self.choices;
+
+        if let Some(filter) = response.selected_filter.as_ref().filter(|_| auto_extension) {
+            let path = &mut response.selection[0];
+            if let (false, Some(ext)) = (filter.matches(path), filter.extensions().next()) {
+                path.as_mut_os_string().push(format!(".{ext}"));
+            }
+        }
+
+        Some(response)
+    }
+}
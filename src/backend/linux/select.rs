@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use super::DialogKind;
+use crate::Error;
+
+/// Dialog backends available on Linux & BSDs.
+///
+/// Which one is used is decided at runtime, see [`set_preferred_backend`] and [`FallbackPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LinuxBackend {
+    /// GTK3 dialogs, requires the `gtk3` feature.
+    Gtk3,
+    /// XDG Desktop Portal, requires the `xdg-portal` feature.
+    ///
+    /// The portal has no message dialogs, kdialog or zenity is used for them instead.
+    Portal,
+    /// The `zenity` helper binary.
+    Zenity,
+    /// The `kdialog` helper binary, used on KDE sessions without a portal.
+    KDialog,
+    /// Prompts on the terminal, used when there is no display and stdin is a terminal, eg. over
+    /// SSH.
+    Terminal,
+}
+
+impl FromStr for LinuxBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "gtk3" | "gtk" => Ok(Self::Gtk3),
+            "portal" | "xdg-portal" | "xdg" => Ok(Self::Portal),
+            "zenity" => Ok(Self::Zenity),
+            "kdialog" => Ok(Self::KDialog),
+            "terminal" | "tty" => Ok(Self::Terminal),
+            other => Err(format!("unknown dialog backend `{other}`")),
+        }
+    }
+}
+
+static PREFERRED_BACKEND: Mutex<Option<LinuxBackend>> = Mutex::new(None);
+
+/// Use `backend` for the dialogs of this process, `None` goes back to picking one automatically.
+///
+/// The preferred backend is tried first, then the ones of the [`FallbackPolicy`]. The
+/// `RFD_BACKEND` environment variable (`gtk3`, `portal`, `zenity`, `kdialog` or `terminal`, also
+/// spelled `gtk`, `xdg-portal`, `xdg` and `tty`) takes precedence over this setting, so users can
+/// always override the choice made by an application.
+///
+/// If the requested backend was not compiled in, the automatic choice is used instead.
+pub fn set_preferred_backend(backend: Option<LinuxBackend>) {
+    *PREFERRED_BACKEND.lock().unwrap() = backend;
+}
+
+/// The order dialog backends are tried in, see [`set_fallback_policy`].
+///
+/// Backends that are not compiled in or not available in this session are skipped: the portal
+/// when no FileChooser portal is running, kdialog outside of KDE sessions, and helper binaries
+/// that are not installed. When a backend fails to show a dialog, the next one is tried.
+///
+/// The default is the XDG Desktop Portal, then kdialog, then GTK3, then zenity, then the
+/// terminal. kdialog can only select a single folder, so it is tried last when picking several.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FallbackPolicy {
+    backends: Vec<LinuxBackend>,
+}
+
+impl FallbackPolicy {
+    /// Tries `backends` in that order, leaving out a backend means it is never used.
+    pub fn new(backends: impl IntoIterator<Item = LinuxBackend>) -> Self {
+        Self {
+            backends: backends.into_iter().collect(),
+        }
+    }
+
+    /// The backends, in the order they are tried.
+    pub fn backends(&self) -> &[LinuxBackend] {
+        &self.backends
+    }
+}
+
+impl Default for FallbackPolicy {
+    fn default() -> Self {
+        Self::new([
+            LinuxBackend::Portal,
+            LinuxBackend::KDialog,
+            LinuxBackend::Gtk3,
+            LinuxBackend::Zenity,
+            LinuxBackend::Terminal,
+        ])
+    }
+}
+
+static FALLBACK_POLICY: Mutex<Option<FallbackPolicy>> = Mutex::new(None);
+
+/// Use `policy` to pick the backend of the dialogs of this process.
+pub fn set_fallback_policy(policy: FallbackPolicy) {
+    *FALLBACK_POLICY.lock().unwrap() = Some(policy);
+}
+
+/// A backend that is compiled in and that a dialog can be sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Selected {
+    #[cfg(feature = "gtk3")]
+    Gtk3,
+    #[cfg(feature = "xdg-portal")]
+    Portal,
+    Zenity,
+    KDialog,
+    Terminal,
+}
+
+impl Selected {
+    fn is_portal(self) -> bool {
+        match self {
+            #[cfg(feature = "xdg-portal")]
+            Selected::Portal => true,
+            _ => false,
+        }
+    }
+}
+
+fn requested_backend() -> Option<LinuxBackend> {
+    let from_env = std::env::var("RFD_BACKEND")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .and_then(|value| match value.parse() {
+            Ok(backend) => Some(backend),
+            Err(err) => {
+                log::warn!("Ignoring RFD_BACKEND: {err}");
+                None
+            }
+        });
+
+    from_env.or(*PREFERRED_BACKEND.lock().unwrap())
+}
+
+fn compiled(backend: LinuxBackend) -> Option<Selected> {
+    match backend {
+        #[cfg(feature = "gtk3")]
+        LinuxBackend::Gtk3 => Some(Selected::Gtk3),
+        #[cfg(feature = "xdg-portal")]
+        LinuxBackend::Portal => Some(Selected::Portal),
+        LinuxBackend::Zenity => Some(Selected::Zenity),
+        LinuxBackend::KDialog => Some(Selected::KDialog),
+        LinuxBackend::Terminal => Some(Selected::Terminal),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Whether the session is a KDE one, going by `XDG_CURRENT_DESKTOP`.
+fn is_kde() -> bool {
+    std::env::var("XDG_CURRENT_DESKTOP").is_ok_and(|desktops| {
+        desktops
+            .split(':')
+            .any(|desktop| desktop.eq_ignore_ascii_case("kde"))
+    })
+}
+
+/// Whether `program` can be found in `PATH`. Looked up once per program.
+pub(crate) fn is_installed(program: &'static str) -> bool {
+    static INSTALLED: Mutex<Option<HashMap<&'static str, bool>>> = Mutex::new(None);
+
+    let mut installed = INSTALLED.lock().unwrap();
+    *installed
+        .get_or_insert_with(HashMap::new)
+        .entry(program)
+        .or_insert_with(|| {
+            let found = std::env::var_os("PATH").is_some_and(|path| {
+                std::env::split_paths(&path).any(|dir| is_executable(&dir.join(program)))
+            });
+            log::debug!("`{program}` is installed: {found}");
+            found
+        })
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+async fn is_available(backend: Selected) -> bool {
+    match backend {
+        #[cfg(feature = "gtk3")]
+        Selected::Gtk3 => true,
+        #[cfg(feature = "xdg-portal")]
+        Selected::Portal => super::super::xdg_desktop_portal::is_available().await,
+        Selected::Zenity => is_installed("zenity"),
+        Selected::KDialog => is_kde() && is_installed("kdialog"),
+        Selected::Terminal => super::terminal::is_available(),
+    }
+}
+
+/// The backends to try for a dialog, in order: the requested one, then the available ones of
+/// the fallback policy. `what` names the kind of dialog in the logs.
+async fn backends(
+    what: &str,
+    with_portal: bool,
+    several_folders: bool,
+) -> Result<Vec<Selected>, Error> {
+    // A backend the user or the application asked for explicitly is tried even if it does not
+    // look available, so the error tells why it could not be used.
+    let requested = requested_backend().and_then(|backend| match compiled(backend) {
+        Some(selected) if selected.is_portal() && !with_portal => None,
+        Some(selected) => Some(selected),
+        None => {
+            log::warn!(
+                "The {backend:?} dialog backend was requested, but rfd was built without it"
+            );
+            None
+        }
+    });
+
+    let policy = FALLBACK_POLICY.lock().unwrap().clone().unwrap_or_default();
+    let mut fallbacks = Vec::new();
+    for backend in policy.backends {
+        let Some(selected) = compiled(backend) else {
+            log::debug!("Skipping the {backend:?} {what} backend, rfd was built without it");
+            continue;
+        };
+
+        if (selected.is_portal() && !with_portal)
+            || Some(selected) == requested
+            || fallbacks.contains(&selected)
+        {
+            continue;
+        }
+
+        if is_available(selected).await {
+            fallbacks.push(selected);
+        } else {
+            log::debug!("Skipping the {backend:?} {what} backend, it is not available");
+        }
+    }
+
+    let backends = ordered(requested, fallbacks, several_folders);
+    if backends.is_empty() {
+        log::warn!("None of the {what} backends of the fallback policy is available");
+        return Err(Error::NoBackend);
+    }
+
+    log::debug!("Trying the {what} backends {backends:?}");
+    Ok(backends)
+}
+
+/// The `requested` backend, then the `fallbacks`. kdialog can only select a single folder, so
+/// it is the last fallback for dialogs picking several, but the requested backend stays first.
+fn ordered(
+    requested: Option<Selected>,
+    mut fallbacks: Vec<Selected>,
+    several_folders: bool,
+) -> Vec<Selected> {
+    if several_folders {
+        fallbacks.sort_by_key(|backend| *backend == Selected::KDialog);
+    }
+
+    requested.into_iter().chain(fallbacks).collect()
+}
+
+/// Picks the backends `kind` file dialogs are sent to, in the order they are tried.
+pub(crate) async fn file_backends(kind: DialogKind) -> Result<Vec<Selected>, Error> {
+    backends("file dialog", true, kind == DialogKind::PickFolders).await
+}
+
+/// Picks the backends message dialogs are sent to, in the order they are tried.
+///
+/// The portal has no message dialogs, so this never has [`Selected::Portal`].
+pub(crate) async fn message_backends() -> Result<Vec<Selected>, Error> {
+    backends("message dialog", false, false).await
+}
+
+/// Whether `err` means the backend could not show the dialog at all, so the next one should be
+/// tried.
+pub(crate) fn is_backend_failure(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Portal(_) | Error::HelperNotFound(_) | Error::HelperFailed { .. } | Error::NoDisplay
+    )
+}
+
+/// Shows a dialog with the first of `backends` that manages to, logging every fallback.
+pub(crate) async fn with_fallback<T, F, Fut>(
+    backends: Vec<Selected>,
+    mut show: F,
+) -> Result<T, Error>
+where
+    F: FnMut(Selected) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut backends = backends.into_iter().peekable();
+
+    while let Some(backend) = backends.next() {
+        match (show(backend).await, backends.peek()) {
+            (Err(err), Some(next)) if is_backend_failure(&err) => {
+                log::debug!("The {backend:?} backend failed ({err}), falling back to {next:?}");
+            }
+            (res, _) => {
+                log::debug!("Used the {backend:?} backend");
+                return res;
+            }
+        }
+    }
+
+    Err(Error::NoBackend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_on_backend_failures_only() {
+        let mut tried = Vec::new();
+        let res = pollster::block_on(with_fallback(
+            vec![Selected::KDialog, Selected::Zenity],
+            |backend| {
+                tried.push(backend);
+                std::future::ready(match backend {
+                    Selected::KDialog => Err(Error::HelperNotFound("kdialog".into())),
+                    _ => Ok(1),
+                })
+            },
+        ));
+        assert_eq!(res.unwrap(), 1);
+        assert_eq!(tried, [Selected::KDialog, Selected::Zenity]);
+
+        let res = pollster::block_on(with_fallback(
+            vec![Selected::KDialog, Selected::Zenity],
+            |_| std::future::ready(Err::<(), _>(Error::TimedOut)),
+        ));
+        assert!(matches!(res, Err(Error::TimedOut)));
+    }
+
+    #[test]
+    fn kdialog_last_for_several_folders() {
+        let fallbacks = vec![Selected::KDialog, Selected::Zenity];
+
+        assert_eq!(
+            ordered(None, fallbacks.clone(), true),
+            [Selected::Zenity, Selected::KDialog]
+        );
+        assert_eq!(ordered(None, fallbacks.clone(), false), fallbacks);
+
+        // A backend the user asked for is never demoted.
+        assert_eq!(
+            ordered(Some(Selected::KDialog), vec![Selected::Zenity], true),
+            [Selected::KDialog, Selected::Zenity]
+        );
+    }
+}
@@ -0,0 +1,670 @@
+//! Dialogs shown as prompts on the terminal, for sessions without a display (eg. over SSH).
+//!
+//! The prompts are read from stdin in raw mode, on a thread of their own, and written to stderr
+//! so they don't mix with the output of the application.
+
+use std::{
+    ffi::OsStr,
+    future::Future,
+    io::{self, IsTerminal, Write},
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use super::{has_display, DialogKind};
+use crate::{
+    message_dialog::{MessageButtons, MessageDialog, MessageLevel},
+    Error, FileDialog, MessageDialogResult,
+};
+
+/// Whether the terminal backend can be used: there is no display, and stdin is a terminal.
+pub fn is_available() -> bool {
+    !has_display() && io::stdin().is_terminal()
+}
+
+struct State<T> {
+    waker: Option<Waker>,
+    data: Option<T>,
+}
+
+/// A prompt running on a background thread.
+///
+/// Dropping the future before the prompt was answered cancels it.
+struct Prompt<T> {
+    state: Arc<Mutex<State<T>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T: Send + 'static> Prompt<Result<T, Error>> {
+    fn spawn(prompt: impl FnOnce(&mut Terminal) -> io::Result<T> + Send + 'static) -> Self {
+        // Only one prompt can use the terminal at a time.
+        static TERMINAL: Mutex<()> = Mutex::new(());
+
+        let state = Arc::new(Mutex::new(State {
+            waker: None,
+            data: None,
+        }));
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        std::thread::spawn({
+            let state = state.clone();
+            let cancelled = cancelled.clone();
+            move || {
+                let _lock = TERMINAL.lock().unwrap_or_else(|err| err.into_inner());
+                let res = Terminal::new(cancelled).and_then(|mut terminal| prompt(&mut terminal));
+
+                let mut state = state.lock().unwrap();
+                state.data = Some(res.map_err(Error::Io));
+
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        });
+
+        Self { state, cancelled }
+    }
+}
+
+impl<T> Drop for Prompt<T> {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> Future for Prompt<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+
+        match state.data.take() {
+            Some(data) => Poll::Ready(data),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Puts stdin in raw mode until dropped.
+struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // No line buffering nor echo, and Ctrl-C cancels the prompt instead of the process.
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+const CTRL_C: u8 = 0x03;
+const CTRL_D: u8 = 0x04;
+const CTRL_U: u8 = 0x15;
+const ESCAPE: u8 = 0x1b;
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7f;
+
+/// Gives the completions of a line.
+type Completion<'a> = &'a dyn Fn(&[u8]) -> Vec<Vec<u8>>;
+
+/// A line editor on the terminal.
+struct Terminal {
+    _raw: RawMode,
+    cancelled: Arc<AtomicBool>,
+    out: io::Stderr,
+}
+
+impl Terminal {
+    fn new(cancelled: Arc<AtomicBool>) -> io::Result<Self> {
+        Ok(Self {
+            _raw: RawMode::enable()?,
+            cancelled,
+            out: io::stderr(),
+        })
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        // Raw mode leaves output processing alone, but be explicit about line starts.
+        self.out.write_all(text.replace('\n', "\r\n").as_bytes())?;
+        self.out.flush()
+    }
+
+    /// The next byte of input, `None` at the end of input or if no byte came within
+    /// `timeout_ms` (`-1` waits forever). Fails once the prompt was cancelled.
+    fn read_byte(&mut self, timeout_ms: i32) -> io::Result<Option<u8>> {
+        let mut waited = 0;
+
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                self.print("\n")?;
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            if timeout_ms >= 0 && waited >= timeout_ms {
+                return Ok(None);
+            }
+
+            let mut fd = libc::pollfd {
+                fd: libc::STDIN_FILENO,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ready = unsafe { libc::poll(&mut fd, 1, 50) };
+            if ready < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+            if ready == 0 {
+                waited += 50;
+                continue;
+            }
+
+            let mut byte = 0u8;
+            let read = unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) };
+            return match read {
+                1 => Ok(Some(byte)),
+                0 => Ok(None),
+                _ => Err(io::Error::last_os_error()),
+            };
+        }
+    }
+
+    /// Skips the rest of an escape sequence, the arrow keys and such are not supported.
+    fn skip_escape_sequence(&mut self) -> io::Result<()> {
+        if let Some(b'[' | b'O') = self.read_byte(50)? {
+            while let Some(byte) = self.read_byte(50)? {
+                if (0x40..=0x7e).contains(&byte) {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn redraw(&mut self, prompt: &str, line: &[u8]) -> io::Result<()> {
+        self.out.write_all(b"\r\x1b[K")?;
+        self.out.write_all(prompt.as_bytes())?;
+        self.out.write_all(line)?;
+        self.out.flush()
+    }
+
+    /// Reads a line, starting from `initial`. `complete` gives the completions of the line when
+    /// Tab is pressed. `None` if the prompt was dismissed with Ctrl-C or Ctrl-D.
+    fn read_line(
+        &mut self,
+        prompt: &str,
+        initial: &[u8],
+        complete: Option<Completion>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let mut line = initial.to_vec();
+        self.redraw(prompt, &line)?;
+
+        loop {
+            match self.read_byte(-1)? {
+                None | Some(CTRL_C) => {
+                    self.print("\n")?;
+                    return Ok(None);
+                }
+                Some(CTRL_D) if line.is_empty() => {
+                    self.print("\n")?;
+                    return Ok(None);
+                }
+                Some(b'\r' | b'\n') => {
+                    self.print("\n")?;
+                    return Ok(Some(line));
+                }
+                Some(BACKSPACE | DELETE) => {
+                    // Remove a whole UTF-8 character.
+                    while let Some(byte) = line.pop() {
+                        if byte & 0xc0 != 0x80 {
+                            break;
+                        }
+                    }
+                }
+                Some(CTRL_U) => line.clear(),
+                Some(ESCAPE) => self.skip_escape_sequence()?,
+                Some(b'\t') => {
+                    let Some(complete) = complete else { continue };
+                    let candidates = complete(&line);
+                    match candidates.as_slice() {
+                        [] => self.out.write_all(b"\x07")?,
+                        [single] => line = single.clone(),
+                        _ => {
+                            let common = common_prefix(&candidates);
+                            if common.len() > line.len() {
+                                line = common.to_vec();
+                            } else {
+                                self.print("\n")?;
+                                let names: Vec<_> = candidates
+                                    .iter()
+                                    .map(|candidate| String::from_utf8_lossy(file_name(candidate)))
+                                    .collect();
+                                self.print(&format!("{}\n", names.join("  ")))?;
+                            }
+                        }
+                    }
+                }
+                Some(byte) if byte >= 0x20 => line.push(byte),
+                Some(_) => {}
+            }
+
+            self.redraw(prompt, &line)?;
+        }
+    }
+}
+
+fn common_prefix(candidates: &[Vec<u8>]) -> &[u8] {
+    let first = &candidates[0];
+    let len = candidates[1..].iter().fold(first.len(), |len, candidate| {
+        first[..len]
+            .iter()
+            .zip(candidate)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    &first[..len]
+}
+
+/// What comes after the last `/` of a completion, the trailing one of folders excluded.
+fn file_name(path: &[u8]) -> &[u8] {
+    let trimmed = path.strip_suffix(b"/").unwrap_or(path);
+    let start = trimmed
+        .iter()
+        .rposition(|&b| b == b'/')
+        .map_or(0, |i| i + 1);
+    &path[start..]
+}
+
+/// The path typed in a prompt, with `~` expanded and relative to `cwd`.
+fn typed_path(cwd: &Path, line: &[u8]) -> PathBuf {
+    let home = || {
+        std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+    };
+
+    match line {
+        b"~" => home(),
+        _ => match line.strip_prefix(b"~/") {
+            Some(rest) => home().join(OsStr::from_bytes(rest)),
+            None => cwd.join(OsStr::from_bytes(line)),
+        },
+    }
+}
+
+/// The folders in `dir`, and the files `accept` lets through, sorted by name. Hidden ones are
+/// left out unless `hidden`.
+fn entries(dir: &Path, hidden: bool, accept: &dyn Fn(&Path) -> bool) -> Vec<(Vec<u8>, bool)> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<_> = read_dir
+        .flatten()
+        .filter(|entry| hidden || !entry.file_name().as_bytes().starts_with(b"."))
+        .filter_map(|entry| {
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            (is_dir || accept(&path)).then(|| (entry.file_name().into_vec(), is_dir))
+        })
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// The completions of the path being typed in `line`.
+fn complete_path(cwd: &Path, line: &[u8], accept: &dyn Fn(&Path) -> bool) -> Vec<Vec<u8>> {
+    let split = line.iter().rposition(|&b| b == b'/').map_or(0, |i| i + 1);
+    let (dir, prefix) = line.split_at(split);
+
+    entries(&typed_path(cwd, dir), prefix.starts_with(b"."), accept)
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(prefix))
+        .map(|(name, is_dir)| {
+            let mut candidate = [dir, &name].concat();
+            if is_dir {
+                candidate.push(b'/');
+            }
+            candidate
+        })
+        .collect()
+}
+
+/// Prints what is in `dir`.
+fn list(terminal: &mut Terminal, dir: &Path, accept: &dyn Fn(&Path) -> bool) -> io::Result<()> {
+    let names: Vec<_> = entries(dir, false, accept)
+        .into_iter()
+        .map(|(name, is_dir)| {
+            let name = String::from_utf8_lossy(&name).into_owned();
+            if is_dir {
+                format!("{name}/")
+            } else {
+                name
+            }
+        })
+        .collect();
+
+    match names.is_empty() {
+        true => terminal.print("  (empty)\n"),
+        false => terminal.print(&format!("  {}\n", names.join("  "))),
+    }
+}
+
+/// Whether a `kind` dialog lets the file `path` be picked, which is when one of its filters
+/// matches it.
+fn accepts(kind: DialogKind, dialog: &FileDialog, path: &Path) -> bool {
+    !kind.is_folder()
+        && (dialog.filters.is_empty() || dialog.filters.iter().any(|f| f.matches(path)))
+}
+
+/// Asks for paths like a `kind` dialog does. Paths are typed relative to a current folder, which
+/// typing a folder moves to in file dialogs.
+fn pick(
+    terminal: &mut Terminal,
+    kind: DialogKind,
+    dialog: &FileDialog,
+) -> io::Result<Option<Vec<PathBuf>>> {
+    let default_title = match kind {
+        DialogKind::PickFile => "Open a file",
+        DialogKind::PickFiles => "Open files",
+        DialogKind::PickFolder => "Select a folder",
+        DialogKind::PickFolders => "Select folders",
+        DialogKind::SaveFile => "Save as",
+    };
+    terminal.print(&format!(
+        "{}\n",
+        dialog.title.as_deref().unwrap_or(default_title)
+    ))?;
+
+    if !dialog.filters.is_empty() {
+        let filters: Vec<_> = dialog
+            .filters
+            .iter()
+            .map(|filter| format!("{} ({})", filter.name, filter.to_globs(false).join(" ")))
+            .collect();
+        terminal.print(&format!("File types: {}\n", filters.join(", ")))?;
+    }
+
+    let hint = match kind {
+        DialogKind::PickFolder => "Enter selects a folder",
+        DialogKind::PickFolders => "Enter selects a folder, an empty line finishes",
+        DialogKind::PickFiles => "Enter opens a folder, an empty line finishes",
+        DialogKind::PickFile | DialogKind::SaveFile => "Enter opens a folder",
+    };
+    terminal.print(&format!("Tab completes, {hint}, Ctrl-C cancels.\n"))?;
+
+    let accept = |path: &Path| accepts(kind, dialog, path);
+
+    let mut cwd = dialog
+        .starting_directory
+        .clone()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("/"));
+    let mut initial = match (kind, &dialog.file_name) {
+        (DialogKind::SaveFile, Some(name)) => name.as_bytes().to_vec(),
+        _ => Vec::new(),
+    };
+    let mut picked = Vec::new();
+
+    if !kind.is_folder() {
+        list(terminal, &cwd, &accept)?;
+    }
+
+    loop {
+        let prompt = match picked.len() {
+            0 => format!("{}> ", cwd.display()),
+            count => format!("[{count} selected] {}> ", cwd.display()),
+        };
+        let complete = |line: &[u8]| complete_path(&cwd, line, &accept);
+        let Some(line) = terminal.read_line(&prompt, &initial, Some(&complete))? else {
+            return Ok(None);
+        };
+        initial.clear();
+
+        if line.is_empty() {
+            if kind.is_multiple() && !picked.is_empty() {
+                return Ok(Some(picked));
+            }
+            continue;
+        }
+
+        let path = typed_path(&cwd, &line);
+
+        if path.is_dir() && !kind.is_folder() {
+            list(terminal, &path, &accept)?;
+            cwd = path;
+            continue;
+        }
+
+        let problem = match kind {
+            _ if kind.is_folder() && !path.is_dir() => Some("Not a folder."),
+            DialogKind::PickFile | DialogKind::PickFiles if !path.is_file() => {
+                Some("No such file.")
+            }
+            DialogKind::PickFile | DialogKind::PickFiles if !accept(&path) => {
+                Some("Not one of the allowed file types.")
+            }
+            DialogKind::SaveFile if !path.parent().is_some_and(Path::is_dir) => {
+                Some("No such folder.")
+            }
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            terminal.print(&format!("{problem}\n"))?;
+            initial = line;
+            continue;
+        }
+
+        if kind == DialogKind::SaveFile && path.exists() {
+            let question = "The file already exists, replace it? [y/N] ";
+            match terminal.read_line(question, b"", None)? {
+                Some(answer) if answer.eq_ignore_ascii_case(b"y") => {}
+                _ => {
+                    initial = line;
+                    continue;
+                }
+            }
+        }
+
+        if !kind.is_multiple() {
+            return Ok(Some(vec![path]));
+        }
+        if !picked.contains(&path) {
+            picked.push(path);
+        }
+    }
+}
+
+/// Shows a file dialog on the terminal.
+pub async fn file_dialog(
+    kind: DialogKind,
+    dialog: &FileDialog,
+) -> Result<Option<Vec<PathBuf>>, Error> {
+    let dialog = dialog.clone();
+    Prompt::spawn(move |terminal| pick(terminal, kind, &dialog)).await
+}
+
+/// The buttons of a message dialog, with what each of them answers.
+fn options(buttons: &MessageButtons) -> Vec<(String, MessageDialogResult)> {
+    let custom = |label: &String| (label.clone(), MessageDialogResult::Custom(label.clone()));
+
+    match buttons {
+        MessageButtons::Ok => vec![("Ok".into(), MessageDialogResult::Ok)],
+        MessageButtons::OkCustom(ok) => vec![custom(ok)],
+        MessageButtons::OkCancel => vec![
+            ("Ok".into(), MessageDialogResult::Ok),
+            ("Cancel".into(), MessageDialogResult::Cancel),
+        ],
+        MessageButtons::OkCancelCustom(ok, cancel) => vec![custom(ok), custom(cancel)],
+        MessageButtons::YesNo => vec![
+            ("Yes".into(), MessageDialogResult::Yes),
+            ("No".into(), MessageDialogResult::No),
+        ],
+        MessageButtons::YesNoCancel => vec![
+            ("Yes".into(), MessageDialogResult::Yes),
+            ("No".into(), MessageDialogResult::No),
+            ("Cancel".into(), MessageDialogResult::Cancel),
+        ],
+        MessageButtons::YesNoCancelCustom(yes, no, cancel) => {
+            vec![custom(yes), custom(no), custom(cancel)]
+        }
+    }
+}
+
+/// The option `answer` picks: its number, its label, or the start of only one label.
+fn chosen(labels: &[&str], answer: &str) -> Option<usize> {
+    let answer = answer.trim().to_lowercase();
+    if answer.is_empty() {
+        return None;
+    }
+
+    if let Ok(number) = answer.parse::<usize>() {
+        return (1..=labels.len()).contains(&number).then(|| number - 1);
+    }
+
+    let labels: Vec<_> = labels.iter().map(|label| label.to_lowercase()).collect();
+    if let Some(index) = labels.iter().position(|label| *label == answer) {
+        return Some(index);
+    }
+
+    let mut matching = (0..labels.len()).filter(|&i| labels[i].starts_with(&answer));
+    match (matching.next(), matching.next()) {
+        (Some(index), None) => Some(index),
+        _ => None,
+    }
+}
+
+fn ask(terminal: &mut Terminal, dialog: &MessageDialog) -> io::Result<MessageDialogResult> {
+    let level = match dialog.level {
+        MessageLevel::Info => "",
+        MessageLevel::Warning => "Warning: ",
+        MessageLevel::Error => "Error: ",
+    };
+    if !dialog.title.is_empty() {
+        terminal.print(&format!("{level}{}\n", dialog.title))?;
+    } else if !level.is_empty() {
+        terminal.print(&format!("{}\n", level.trim_end_matches(": ")))?;
+    }
+    terminal.print(&format!("{}\n", dialog.description))?;
+
+    let mut options = options(&dialog.buttons);
+    let labels: Vec<_> = options.iter().map(|(label, _)| label.as_str()).collect();
+    let prompt = match labels.as_slice() {
+        [single] => format!("[{single}] "),
+        _ => {
+            let numbered: Vec<_> = (1..)
+                .zip(&labels)
+                .map(|(i, label)| format!("{i}) {label}"))
+                .collect();
+            format!("{}? ", numbered.join("  "))
+        }
+    };
+
+    // Dismissing the prompt answers like the last button, the one closing the dialog does.
+    let dismissed = options.len() - 1;
+    let index = loop {
+        let Some(answer) = terminal.read_line(&prompt, b"", None)? else {
+            break dismissed;
+        };
+        if options.len() == 1 {
+            break 0;
+        }
+        match chosen(&labels, &String::from_utf8_lossy(&answer)) {
+            Some(index) => break index,
+            None => terminal.print(&format!("Please answer {}.\n", labels.join(", ")))?,
+        }
+    };
+
+    Ok(options.swap_remove(index).1)
+}
+
+/// Shows a message dialog on the terminal.
+pub async fn message(dialog: MessageDialog) -> Result<MessageDialogResult, Error> {
+    Prompt::spawn(move |terminal| ask(terminal, &dialog)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FilterPattern;
+
+    #[test]
+    fn chosen_option() {
+        let labels = ["Save", "Don't save", "Cancel"];
+        assert_eq!(chosen(&labels, "2"), Some(1));
+        assert_eq!(chosen(&labels, "4"), None);
+        assert_eq!(chosen(&labels, "cancel"), Some(2));
+        assert_eq!(chosen(&labels, "c"), Some(2));
+        assert_eq!(chosen(&labels, "d"), Some(1));
+        assert_eq!(chosen(&["Save", "Skip"], "s"), None);
+    }
+
+    #[test]
+    fn accepted_files() {
+        let all = FileDialog::new().add_filter("All", &["*"]);
+        assert!(accepts(DialogKind::PickFile, &all, Path::new("/a/notes")));
+        assert!(!accepts(
+            DialogKind::PickFolder,
+            &all,
+            Path::new("/a/notes")
+        ));
+
+        let dialog = FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .add_pattern_filter("Makefiles", [FilterPattern::glob("Makefile*")]);
+        assert!(accepts(
+            DialogKind::SaveFile,
+            &dialog,
+            Path::new("Makefile.am")
+        ));
+        assert!(accepts(DialogKind::SaveFile, &dialog, Path::new("a.TXT")));
+        assert!(!accepts(DialogKind::SaveFile, &dialog, Path::new("a.rs")));
+    }
+
+    #[test]
+    fn path_completion() {
+        let dir = std::env::temp_dir().join(format!("rfd-terminal-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("photos")).unwrap();
+        std::fs::write(dir.join("photo.png"), "").unwrap();
+        std::fs::write(dir.join("photo.txt"), "").unwrap();
+
+        let png = |path: &Path| path.extension().is_some_and(|ext| ext == "png");
+        let completions: Vec<_> = complete_path(&dir, b"pho", &png)
+            .into_iter()
+            .map(|candidate| String::from_utf8(candidate).unwrap())
+            .collect();
+        assert_eq!(completions, ["photo.png", "photos/"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,151 +1,238 @@
-use std::{error::Error, fmt::Display, path::PathBuf, process::Command};
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+    process::Command,
+    time::Duration,
+};
+
+use raw_window_handle::RawWindowHandle;
 
+use super::mime;
 use crate::{
     file_dialog::Filter,
     message_dialog::{MessageButtons, MessageLevel},
-    FileDialog, MessageDialogResult,
+    Error, FileDialog, MessageDialogResult,
 };
 
-#[derive(Debug)]
-pub enum ZenityError {
-    Io(std::io::Error),
-    FromUtf8Error(std::string::FromUtf8Error),
-}
-
-impl Error for ZenityError {}
+pub type ZenityResult<T> = Result<T, Error>;
 
-impl Display for ZenityError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ZenityError::Io(io) => write!(f, "{io}"),
-            ZenityError::FromUtf8Error(err) => err.fmt(f),
-        }
-    }
-}
+/// Separates the paths printed for `--multiple`. Zenity prints normalized paths, that never
+/// contain `//`, so it can't be mistaken for a part of a path.
+const SEPARATOR: &str = "\n//\n";
 
-impl From<std::io::Error> for ZenityError {
-    fn from(value: std::io::Error) -> Self {
-        Self::Io(value)
-    }
+#[cfg(test)]
+thread_local! {
+    /// The fake zenity the tests of this thread run instead of the real one, and whether it has
+    /// a display to show its dialogs on.
+    static FAKE_ZENITY: std::cell::RefCell<Option<(PathBuf, bool)>> = const { std::cell::RefCell::new(None) };
 }
 
-impl From<std::string::FromUtf8Error> for ZenityError {
-    fn from(value: std::string::FromUtf8Error) -> Self {
-        Self::FromUtf8Error(value)
+/// Whether there is a display for zenity to show its dialogs on.
+fn has_display() -> bool {
+    #[cfg(test)]
+    if let Some((_, display)) = FAKE_ZENITY.with(|fake| fake.borrow().clone()) {
+        return display;
     }
+    super::has_display()
 }
 
-pub type ZenityResult<T> = Result<T, ZenityError>;
-
-fn command() -> Command {
+fn command(timeout: Option<Duration>) -> Command {
+    #[cfg(not(test))]
     let mut cmd = Command::new("zenity");
+    #[cfg(test)]
+    let mut cmd = Command::new(
+        FAKE_ZENITY
+            .with(|fake| fake.borrow().clone())
+            .map_or_else(|| "zenity".into(), |(zenity, _)| zenity),
+    );
     cmd.arg("--no-markup");
+
+    if let Some(timeout) = timeout {
+        // Zenity counts in whole seconds, never give up before the requested time.
+        let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+        cmd.arg(format!("--timeout={}", secs.max(1)));
+    }
+
     cmd
 }
 
-fn add_filters(command: &mut Command, filters: &[Filter]) {
-    for f in filters.iter() {
+/// Zenity selects the first filter, so the current one goes first.
+fn add_filters(command: &mut Command, filters: &[Filter], current: Option<&str>) {
+    let (current, others): (Vec<_>, Vec<_>) = filters
+        .iter()
+        .partition(|filter| Some(filter.name.as_str()) == current);
+
+    for f in current.into_iter().chain(others) {
         command.arg("--file-filter");
 
-        let extensions: Vec<_> = f
-            .extensions
-            .iter()
-            .map(|ext| format!("*.{}", ext))
+        // Zenity takes everything up to the first `|` as the name, and splits the patterns on
+        // spaces. A look-alike stands in for `|` in names, and spaces in patterns are escaped.
+        let name = f.name.replace('|', "\u{2223}");
+        let patterns: Vec<_> = f
+            .to_globs(true)
+            .into_iter()
+            .chain(f.mime_types().flat_map(mime::patterns))
+            .map(|pattern| pattern.replace(' ', "[ ]"))
             .collect();
 
-        command.arg(format!("{} | {}", f.name, extensions.join(" ")));
+        command.arg(format!("{name} | {}", patterns.join(" ")));
+    }
+}
+
+fn add_accept_label(command: &mut Command, label: &Option<String>) {
+    if let Some(label) = label.as_ref() {
+        command.arg("--ok-label");
+        command.arg(label);
     }
 }
 
-fn add_filename(command: &mut Command, file_name: &Option<String>) {
-    if let Some(name) = file_name.as_ref() {
-        command.arg("--filename");
-        command.arg(name);
+fn add_title(command: &mut Command, title: &Option<String>) {
+    if let Some(title) = title.as_ref() {
+        command.arg("--title");
+        command.arg(title);
     }
 }
 
-async fn run(command: Command) -> ZenityResult<Option<String>> {
-    let res = super::async_command::AsyncCommand::spawn(command).await?;
-    let buffer = String::from_utf8(res.stdout)?;
+/// Zenity takes the starting directory and the file name as a single path, the directory
+/// alone needs a trailing slash.
+fn add_filename(command: &mut Command, dir: &Option<PathBuf>, file_name: &Option<OsString>) {
+    let path = match (dir, file_name) {
+        (Some(dir), Some(name)) => dir.join(name).into_os_string(),
+        (Some(dir), None) => {
+            let mut dir = dir.clone().into_os_string();
+            if !dir.as_bytes().ends_with(b"/") {
+                dir.push("/");
+            }
+            dir
+        }
+        (None, Some(name)) => name.clone(),
+        (None, None) => return,
+    };
 
-    Ok((res.status.success() || !buffer.is_empty()).then_some(buffer))
+    command.arg("--filename");
+    command.arg(path);
 }
 
-pub async fn pick_file(dialog: &FileDialog) -> ZenityResult<Option<PathBuf>> {
-    let mut command = command();
-    command.arg("--file-selection");
+/// Zenity can only be attached to X11 windows.
+fn add_parent(command: &mut Command, parent: Option<RawWindowHandle>) {
+    let xid = match parent {
+        Some(RawWindowHandle::Xlib(handle)) => handle.window.to_string(),
+        Some(RawWindowHandle::Xcb(handle)) => handle.window.get().to_string(),
+        _ => return,
+    };
 
-    add_filters(&mut command, &dialog.filters);
-    add_filename(&mut command, &dialog.file_name);
+    command.arg("--modal");
+    command.arg(format!("--attach={xid}"));
+}
 
-    run(command).await.map(|res| {
-        res.map(|buffer| {
-            let trimed = buffer.trim();
-            trimed.into()
-        })
-    })
+/// A `--file-selection` command with the options of `dialog`, and `args`.
+fn file_selection(dialog: &FileDialog, args: &[&str]) -> Command {
+    let mut command = command(dialog.timeout);
+    command.arg("--file-selection");
+    command.args(args);
+
+    add_title(&mut command, &dialog.title);
+    add_filters(
+        &mut command,
+        &dialog.filters,
+        dialog.current_filter.as_deref(),
+    );
+    add_filename(&mut command, &dialog.starting_directory, &dialog.file_name);
+    add_accept_label(&mut command, &dialog.accept_label);
+    add_parent(&mut command, dialog.parent);
+
+    command
 }
 
-pub async fn pick_files(dialog: &FileDialog) -> ZenityResult<Vec<PathBuf>> {
-    let mut command = command();
-    command.args(["--file-selection", "--multiple"]);
+async fn run(command: Command) -> ZenityResult<Option<Vec<u8>>> {
+    if !has_display() {
+        return Err(Error::NoDisplay);
+    }
 
-    add_filters(&mut command, &dialog.filters);
-    add_filename(&mut command, &dialog.file_name);
+    let res = super::async_command::AsyncCommand::spawn(command)
+        .await
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => Error::HelperNotFound("zenity".into()),
+            _ => Error::Io(err),
+        })?;
+
+    // Zenity exits with 1 when the dialog is cancelled (or the extra button was pressed),
+    // and with 5 when `--timeout` expired. Anything else but 0 means it could not show the
+    // dialog in the first place.
+    if res.status.code() == Some(5) {
+        return Err(Error::TimedOut);
+    }
+    if !matches!(res.status.code(), Some(0 | 1)) {
+        return Err(Error::HelperFailed {
+            program: "zenity".into(),
+            code: res.status.code(),
+            stderr: String::from_utf8_lossy(&res.stderr).trim().to_owned(),
+        });
+    }
 
-    run(command).await.map(|res| {
-        res.map(|buffer| {
-            let list = buffer.trim().split('|').map(PathBuf::from).collect();
-            list
-        })
-        .unwrap_or_default()
-    })
-}
+    let buffer = res.stdout;
 
-pub async fn pick_folder(dialog: &FileDialog) -> ZenityResult<Option<PathBuf>> {
-    let mut command = command();
-    command.args(["--file-selection", "--directory"]);
+    Ok((res.status.success() || !buffer.is_empty()).then_some(buffer))
+}
 
-    add_filters(&mut command, &dialog.filters);
-    add_filename(&mut command, &dialog.file_name);
+/// The paths zenity printed, as bytes since they need not be UTF-8. Its output ends with a new
+/// line that is not a part of them.
+fn paths(output: &[u8]) -> Vec<PathBuf> {
+    let mut output = output.strip_suffix(b"\n").unwrap_or(output);
+    let separator = SEPARATOR.as_bytes();
+
+    let mut paths = Vec::new();
+    while !output.is_empty() {
+        let end = output
+            .windows(separator.len())
+            .position(|window| window == separator)
+            .unwrap_or(output.len());
+        if end > 0 {
+            paths.push(PathBuf::from(OsStr::from_bytes(&output[..end])));
+        }
+        output = output.get(end + separator.len()..).unwrap_or_default();
+    }
+    paths
+}
 
-    run(command).await.map(|res| {
-        res.map(|buffer| {
-            let trimed = buffer.trim();
-            trimed.into()
-        })
-    })
+async fn run_path(command: Command) -> ZenityResult<Option<PathBuf>> {
+    let res = run(command).await?;
+    Ok(res.and_then(|output| paths(&output).into_iter().next()))
 }
 
-pub async fn pick_folders(dialog: &FileDialog) -> ZenityResult<Vec<PathBuf>> {
-    let mut command = command();
-    command.args(["--file-selection", "--directory", "--multiple"]);
+async fn run_paths(command: Command) -> ZenityResult<Option<Vec<PathBuf>>> {
+    let res = run(command).await?;
+    Ok(res
+        .map(|output| paths(&output))
+        .filter(|paths| !paths.is_empty()))
+}
 
-    add_filters(&mut command, &dialog.filters);
-    add_filename(&mut command, &dialog.file_name);
+pub async fn pick_file(dialog: &FileDialog) -> ZenityResult<Option<PathBuf>> {
+    run_path(file_selection(dialog, &[])).await
+}
 
-    run(command).await.map(|res| {
-        res.map(|buffer| {
-            let list = buffer.trim().split('|').map(PathBuf::from).collect();
-            list
-        })
-        .unwrap_or_default()
-    })
+pub async fn pick_files(dialog: &FileDialog) -> ZenityResult<Option<Vec<PathBuf>>> {
+    let separator = format!("--separator={SEPARATOR}");
+    run_paths(file_selection(dialog, &["--multiple", &separator])).await
 }
 
-pub async fn save_file(dialog: &FileDialog) -> ZenityResult<Option<PathBuf>> {
-    let mut command = command();
-    command.args(["--file-selection", "--save", "--confirm-overwrite"]);
+pub async fn pick_folder(dialog: &FileDialog) -> ZenityResult<Option<PathBuf>> {
+    run_path(file_selection(dialog, &["--directory"])).await
+}
 
-    add_filters(&mut command, &dialog.filters);
-    add_filename(&mut command, &dialog.file_name);
+pub async fn pick_folders(dialog: &FileDialog) -> ZenityResult<Option<Vec<PathBuf>>> {
+    let separator = format!("--separator={SEPARATOR}");
+    run_paths(file_selection(
+        dialog,
+        &["--directory", "--multiple", &separator],
+    ))
+    .await
+}
 
-    run(command).await.map(|res| {
-        res.map(|buffer| {
-            let trimed = buffer.trim();
-            trimed.into()
-        })
-    })
+pub async fn save_file(dialog: &FileDialog) -> ZenityResult<Option<PathBuf>> {
+    run_path(file_selection(dialog, &["--save", "--confirm-overwrite"])).await
 }
 
 pub async fn message(
@@ -153,6 +240,7 @@ pub async fn message(
     btns: &MessageButtons,
     title: &str,
     description: &str,
+    timeout: Option<Duration>,
 ) -> ZenityResult<MessageDialogResult> {
     let cmd = match level {
         MessageLevel::Info => "--info",
@@ -166,7 +254,7 @@ pub async fn message(
         _ => None,
     };
 
-    let mut command = command();
+    let mut command = command(timeout);
     command.args([cmd, "--title", title, "--text", description]);
 
     if let Some(ok) = ok_label {
@@ -183,8 +271,9 @@ pub async fn question(
     btns: &MessageButtons,
     title: &str,
     description: &str,
+    timeout: Option<Duration>,
 ) -> ZenityResult<MessageDialogResult> {
-    let mut command = command();
+    let mut command = command(timeout);
     command.args(["--question", "--title", title, "--text", description]);
 
     match btns {
@@ -237,7 +326,137 @@ pub async fn question(
 
 #[cfg(test)]
 mod tests {
-    use crate::FileDialog;
+    use crate::{Error, FileDialog};
+    use raw_window_handle::{RawWindowHandle, XlibWindowHandle};
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    #[test]
+    fn file_selection_options() {
+        let mut dialog = FileDialog::default()
+            .set_title("Export")
+            .set_directory("/tmp")
+            .set_file_name("a.png");
+        dialog.parent = Some(RawWindowHandle::Xlib(XlibWindowHandle::new(42)));
+
+        let command = super::file_selection(&dialog, &["--save"]);
+        let args: Vec<_> = command.get_args().collect();
+        assert!(args.windows(2).any(|args| args == ["--title", "Export"]));
+        assert!(args
+            .windows(2)
+            .any(|args| args == ["--filename", "/tmp/a.png"]));
+        assert!(args
+            .windows(2)
+            .any(|args| args == ["--modal", "--attach=42"]));
+
+        let command = super::file_selection(&FileDialog::default().set_directory("/tmp"), &[]);
+        assert!(command.get_args().any(|arg| arg == "/tmp/"));
+    }
+
+    #[test]
+    fn escaped_filters() {
+        let dialog = FileDialog::default()
+            .add_filter("Text | Markdown", &["txt", "md"])
+            .add_filter("Backup", &["old copy"]);
+
+        let command = super::file_selection(&dialog, &[]);
+        let args: Vec<_> = command.get_args().collect();
+        assert!(args.windows(2).any(|args| args
+            == [
+                "--file-filter",
+                "Text \u{2223} Markdown | *.[tT][xX][tT] *.[mM][dD]"
+            ]));
+        assert!(args.windows(2).any(|args| args
+            == [
+                "--file-filter",
+                "Backup | *.[oO][lL][dD][ ][cC][oO][pP][yY]"
+            ]));
+    }
+
+    /// Runs `f` with a fake zenity that prints `paths` joined with the `--separator` it is given.
+    fn with_fake_zenity<T, P: AsRef<OsStr>>(paths: &[P], f: impl FnOnce() -> T) -> T {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "rfd-zenity-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fake = dir.join("zenity");
+
+        // The paths are written as bytes, they need not be UTF-8.
+        let mut script = b"#!/bin/sh\n\
+            sep='|'\n\
+            for arg; do case \"$arg\" in --separator=*) sep=\"${arg#--separator=}\";; esac; done\n\
+            first=1\n\
+            for path in"
+            .to_vec();
+        for path in paths {
+            script.extend(b" '");
+            for &byte in path.as_ref().as_bytes() {
+                match byte {
+                    b'\'' => script.extend(b"'\\''"),
+                    byte => script.push(byte),
+                }
+            }
+            script.push(b'\'');
+        }
+        script.extend(
+            b"; do\n\
+                [ $first = 1 ] || printf '%s' \"$sep\"\n\
+                printf '%s' \"$path\"\n\
+                first=0\n\
+            done\n\
+            printf '\\n'\n",
+        );
+        std::fs::write(&fake, script).unwrap();
+        std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        super::FAKE_ZENITY.with(|zenity| *zenity.borrow_mut() = Some((fake, true)));
+        let res = f();
+        super::FAKE_ZENITY.with(|zenity| *zenity.borrow_mut() = None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+        res
+    }
+
+    #[test]
+    fn no_display() {
+        super::FAKE_ZENITY.with(|zenity| *zenity.borrow_mut() = Some(("zenity".into(), false)));
+        let picked = pollster::block_on(super::pick_file(&FileDialog::default()));
+        super::FAKE_ZENITY.with(|zenity| *zenity.borrow_mut() = None);
+
+        assert!(matches!(picked, Err(Error::NoDisplay)));
+    }
+
+    #[test]
+    fn multiple_selection() {
+        let paths = ["/tmp/a|b.txt", "/tmp/new\nline\n", "/tmp/ trailing space "];
+        let picked = with_fake_zenity(&paths, || {
+            pollster::block_on(super::pick_files(&FileDialog::default())).unwrap()
+        });
+        assert_eq!(
+            picked.unwrap(),
+            paths.map(std::path::PathBuf::from).to_vec()
+        );
+
+        let picked = with_fake_zenity(&["/", "/tmp"], || {
+            pollster::block_on(super::pick_folders(&FileDialog::default())).unwrap()
+        });
+        assert_eq!(picked.unwrap(), ["/", "/tmp"].map(std::path::PathBuf::from));
+
+        let picked = with_fake_zenity(&["/tmp/a|b.txt\n"], || {
+            pollster::block_on(super::pick_file(&FileDialog::default())).unwrap()
+        });
+        assert_eq!(picked.unwrap(), std::path::Path::new("/tmp/a|b.txt\n"));
+
+        // A Latin-1 name, as left behind by older systems.
+        let latin1 = OsStr::from_bytes(b"/tmp/caf\xe9.txt");
+        let picked = with_fake_zenity(&[latin1], || {
+            pollster::block_on(super::pick_file(&FileDialog::default())).unwrap()
+        });
+        assert_eq!(picked.unwrap(), latin1);
+    }
 
     #[test]
     #[ignore]
@@ -247,6 +466,7 @@ mod tests {
             &crate::message_dialog::MessageButtons::Ok,
             "hi",
             "me",
+            None,
         ))
         .unwrap();
         pollster::block_on(super::message(
@@ -254,6 +474,7 @@ mod tests {
             &crate::message_dialog::MessageButtons::Ok,
             "hi",
             "me",
+            None,
         ))
         .unwrap();
         pollster::block_on(super::message(
@@ -261,6 +482,7 @@ mod tests {
             &crate::message_dialog::MessageButtons::Ok,
             "hi",
             "me",
+            None,
         ))
         .unwrap();
     }
@@ -272,12 +494,14 @@ mod tests {
             &crate::message_dialog::MessageButtons::OkCancel,
             "hi",
             "me",
+            None,
         ))
         .unwrap();
         pollster::block_on(super::question(
             &crate::message_dialog::MessageButtons::YesNo,
             "hi",
             "me",
+            None,
         ))
         .unwrap();
     }
@@ -1,4 +1,5 @@
 use core::str;
+use std::ffi::OsStr;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -115,7 +116,12 @@ trait PanelExt {
         let mut exts: Vec<String> = Vec::new();
 
         for filter in opt.filters.iter() {
-            exts.append(&mut filter.extensions.to_vec());
+            exts.extend(filter.extensions().map(String::from));
+        }
+
+        // Only glob and MIME type filters, which have no macOS equivalent.
+        if exts.is_empty() {
+            return;
         }
 
         let f_raw: Vec<_> = exts.iter().map(|ext| NSString::from_str(&ext)).collect();
@@ -127,7 +133,7 @@ trait PanelExt {
         }
     }
 
-    fn set_path(&self, path: &Path, file_name: Option<&str>) {
+    fn set_path(&self, path: &Path, file_name: Option<&OsStr>) {
         // if file_name is some, and path is a dir
         let path = if let (Some(name), true) = (file_name, path.is_dir()) {
             let mut path = path.to_owned();
@@ -156,6 +162,10 @@ trait PanelExt {
     fn set_title(&self, title: &str) {
         unsafe { self.panel().setMessage(Some(&NSString::from_str(title))) }
     }
+
+    fn set_accept_label(&self, label: &str) {
+        unsafe { self.panel().setPrompt(Some(&NSString::from_str(label))) }
+    }
 }
 
 impl PanelExt for Id<NSSavePanel> {
@@ -183,13 +193,17 @@ impl Panel {
         }
 
         if let Some(file_name) = &opt.file_name {
-            panel.set_file_name(file_name);
+            panel.set_file_name(&file_name.to_string_lossy());
         }
 
         if let Some(title) = &opt.title {
             panel.set_title(title);
         }
 
+        if let Some(label) = &opt.accept_label {
+            panel.set_accept_label(label);
+        }
+
         if let Some(can) = opt.can_create_directories {
             panel.set_can_create_directories(can);
         }
@@ -212,13 +226,17 @@ impl Panel {
         }
 
         if let Some(file_name) = &opt.file_name {
-            panel.set_file_name(file_name);
+            panel.set_file_name(&file_name.to_string_lossy());
         }
 
         if let Some(title) = &opt.title {
             panel.set_title(title);
         }
 
+        if let Some(label) = &opt.accept_label {
+            panel.set_accept_label(label);
+        }
+
         if let Some(can) = opt.can_create_directories {
             panel.set_can_create_directories(can);
         }
@@ -237,6 +255,10 @@ impl Panel {
             panel.set_title(title);
         }
 
+        if let Some(label) = &opt.accept_label {
+            panel.set_accept_label(label);
+        }
+
         let can = opt.can_create_directories.unwrap_or(true);
         panel.set_can_create_directories(can);
 
@@ -257,6 +279,10 @@ impl Panel {
             panel.set_title(title);
         }
 
+        if let Some(label) = &opt.accept_label {
+            panel.set_accept_label(label);
+        }
+
         let can = opt.can_create_directories.unwrap_or(true);
         panel.set_can_create_directories(can);
 
@@ -282,6 +308,10 @@ impl Panel {
             panel.set_title(title);
         }
 
+        if let Some(label) = &opt.accept_label {
+            panel.set_accept_label(label);
+        }
+
         if let Some(can) = opt.can_create_directories {
             panel.set_can_create_directories(can);
         }
@@ -75,11 +75,10 @@ impl<'a> WasmDialog<'a> {
                 let mut accept: Vec<String> = Vec::new();
 
                 for filter in dialog.filters.iter() {
-                    accept.append(&mut filter.extensions.to_vec());
+                    accept.extend(filter.extensions().map(|ext| format!(".{ext}")));
+                    accept.extend(filter.mime_types().map(String::from));
                 }
 
-                accept.iter_mut().for_each(|ext| ext.insert_str(0, "."));
-
                 input.set_accept(&accept.join(","));
 
                 card.append_child(&input).unwrap();
@@ -95,7 +94,11 @@ impl<'a> WasmDialog<'a> {
                 card.append_child(&output).unwrap();
                 HtmlIoElement::Output {
                     element: output,
-                    name: dialog.file_name.clone().unwrap_or_default(),
+                    name: dialog
+                        .file_name
+                        .as_ref()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
                     data,
                 }
             }
@@ -186,7 +186,8 @@ pub(super) struct IFileDialogV {
         cfiletypes: u32,
         rgfilterspec: *const COMDLG_FILTERSPEC,
     ) -> HRESULT,
-    SetFileTypeIndex: unsafe extern "system" fn(this: *mut c_void, ifiletype: u32) -> HRESULT,
+    pub(super) SetFileTypeIndex:
+        unsafe extern "system" fn(this: *mut c_void, ifiletype: u32) -> HRESULT,
     GetFileTypeIndex: unsafe extern "system" fn(this: *mut c_void, pifiletype: *mut u32) -> HRESULT,
     Advise: unsafe extern "system" fn(
         this: *mut c_void,
@@ -207,7 +208,8 @@ pub(super) struct IFileDialogV {
         unsafe extern "system" fn(this: *mut c_void, pszname: PCWSTR) -> HRESULT,
     GetFileName: unsafe extern "system" fn(this: *mut c_void, pszname: *mut PWSTR) -> HRESULT,
     pub(super) SetTitle: unsafe extern "system" fn(this: *mut c_void, psztitle: PCWSTR) -> HRESULT,
-    SetOkButtonLabel: unsafe extern "system" fn(this: *mut c_void, psztext: PCWSTR) -> HRESULT,
+    pub(super) SetOkButtonLabel:
+        unsafe extern "system" fn(this: *mut c_void, psztext: PCWSTR) -> HRESULT,
     SetFileNameLabel: unsafe extern "system" fn(this: *mut c_void, pszlabel: PCWSTR) -> HRESULT,
     pub(super) GetResult:
         unsafe extern "system" fn(this: *mut c_void, ppsi: *mut IShellItem) -> HRESULT,
@@ -17,7 +17,11 @@ use windows_sys::{
     },
 };
 
-use std::{ffi::c_void, path::PathBuf};
+use std::{
+    ffi::{c_void, OsString},
+    os::windows::ffi::OsStrExt,
+    path::PathBuf,
+};
 
 use raw_window_handle::RawWindowHandle;
 
@@ -99,6 +103,18 @@ impl DialogInner {
         wrap_err((v.SetFileTypes)(d, specs.len() as _, specs.as_ptr()))
     }
 
+    #[inline]
+    unsafe fn set_file_type_index(&self, index: u32) -> Result<()> {
+        let (d, v) = self.fd();
+        wrap_err((v.SetFileTypeIndex)(d, index))
+    }
+
+    #[inline]
+    unsafe fn set_ok_button_label(&self, label: &[u16]) -> Result<()> {
+        let (d, v) = self.fd();
+        wrap_err((v.SetOkButtonLabel)(d, label.as_ptr()))
+    }
+
     #[inline]
     unsafe fn set_filename(&self, fname: &[u16]) -> Result<()> {
         let (d, v) = self.fd();
@@ -172,35 +188,41 @@ impl IDialog {
         Ok(Self(dialog, parent))
     }
 
-    fn add_filters(&self, filters: &[crate::file_dialog::Filter]) -> Result<()> {
+    fn add_filters(
+        &self,
+        filters: &[crate::file_dialog::Filter],
+        current: Option<&str>,
+    ) -> Result<()> {
+        let current = current.and_then(|name| filters.iter().find(|f| f.name == name));
+
         {
-            let Some(first_filter) = filters.first() else {
+            let Some(first_filter) = current.or(filters.first()) else {
                 return Ok(());
             };
-            if let Some(first_extension) = first_filter.extensions.first() {
+            if let Some(first_extension) = first_filter.extensions().next() {
                 let extension = str_to_vec_u16(first_extension);
                 unsafe { self.0.set_default_extension(&extension)? }
             }
         }
 
+        let mut current_index = None;
         let f_list = {
             let mut f_list = Vec::new();
-            let mut ext_string = String::new();
 
             for f in filters.iter() {
-                let name = str_to_vec_u16(&f.name);
-                ext_string.clear();
-
-                for ext in &f.extensions {
-                    use std::fmt::Write;
-                    // This is infallible for String (barring OOM)
-                    let _ = write!(&mut ext_string, "*.{ext};");
+                // Windows matches case-insensitively, and has no equivalent of MIME types.
+                let globs = f.to_globs(false);
+                if globs.is_empty() {
+                    continue;
                 }
 
-                // pop trailing ;
-                ext_string.pop();
+                if current.is_some_and(|current| std::ptr::eq(current, f)) {
+                    // The index of a file type is one-based.
+                    current_index = Some(f_list.len() as u32 + 1);
+                }
 
-                f_list.push((name, str_to_vec_u16(&ext_string)));
+                let name = str_to_vec_u16(&f.name);
+                f_list.push((name, str_to_vec_u16(&globs.join(";"))));
             }
             f_list
         };
@@ -213,8 +235,15 @@ impl IDialog {
             })
             .collect();
 
-        unsafe {
-            self.0.set_file_types(&spec)?;
+        if !spec.is_empty() {
+            unsafe {
+                self.0.set_file_types(&spec)?;
+            }
+        }
+        if let Some(index) = current_index {
+            unsafe {
+                self.0.set_file_type_index(index)?;
+            }
         }
         Ok(())
     }
@@ -250,9 +279,10 @@ impl IDialog {
         Ok(())
     }
 
-    fn set_file_name(&self, file_name: &Option<String>) -> Result<()> {
-        if let Some(path) = file_name {
-            let wide_path = str_to_vec_u16(path);
+    fn set_file_name(&self, file_name: &Option<OsString>) -> Result<()> {
+        if let Some(name) = file_name {
+            let mut wide_path: Vec<u16> = name.encode_wide().collect();
+            wide_path.push(0);
 
             unsafe {
                 self.0.set_filename(&wide_path)?;
@@ -272,6 +302,17 @@ impl IDialog {
         Ok(())
     }
 
+    fn set_accept_label(&self, label: &Option<String>) -> Result<()> {
+        if let Some(label) = label {
+            let wide_label = str_to_vec_u16(label);
+
+            unsafe {
+                self.0.set_ok_button_label(&wide_label)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_results(&self) -> Result<Vec<PathBuf>> {
         unsafe { self.0.get_results() }
     }
@@ -289,10 +330,11 @@ impl IDialog {
     pub fn build_pick_file(opt: &FileDialog) -> Result<Self> {
         let dialog = IDialog::new_open_dialog(opt)?;
 
-        dialog.add_filters(&opt.filters)?;
+        dialog.add_filters(&opt.filters, opt.current_filter.as_deref())?;
         dialog.set_path(&opt.starting_directory)?;
         dialog.set_file_name(&opt.file_name)?;
         dialog.set_title(&opt.title)?;
+        dialog.set_accept_label(&opt.accept_label)?;
 
         Ok(dialog)
     }
@@ -300,10 +342,11 @@ impl IDialog {
     pub fn build_save_file(opt: &FileDialog) -> Result<Self> {
         let dialog = IDialog::new_save_dialog(opt)?;
 
-        dialog.add_filters(&opt.filters)?;
+        dialog.add_filters(&opt.filters, opt.current_filter.as_deref())?;
         dialog.set_path(&opt.starting_directory)?;
         dialog.set_file_name(&opt.file_name)?;
         dialog.set_title(&opt.title)?;
+        dialog.set_accept_label(&opt.accept_label)?;
 
         Ok(dialog)
     }
@@ -313,6 +356,7 @@ impl IDialog {
 
         dialog.set_path(&opt.starting_directory)?;
         dialog.set_title(&opt.title)?;
+        dialog.set_accept_label(&opt.accept_label)?;
 
         unsafe {
             dialog.0.set_options(FOS_PICKFOLDERS)?;
@@ -326,6 +370,7 @@ impl IDialog {
 
         dialog.set_path(&opt.starting_directory)?;
         dialog.set_title(&opt.title)?;
+        dialog.set_accept_label(&opt.accept_label)?;
         let opts = FOS_PICKFOLDERS | FOS_ALLOWMULTISELECT;
 
         unsafe {
@@ -338,10 +383,11 @@ impl IDialog {
     pub fn build_pick_files(opt: &FileDialog) -> Result<Self> {
         let dialog = IDialog::new_open_dialog(opt)?;
 
-        dialog.add_filters(&opt.filters)?;
+        dialog.add_filters(&opt.filters, opt.current_filter.as_deref())?;
         dialog.set_path(&opt.starting_directory)?;
         dialog.set_file_name(&opt.file_name)?;
         dialog.set_title(&opt.title)?;
+        dialog.set_accept_label(&opt.accept_label)?;
 
         unsafe {
             dialog.0.set_options(FOS_ALLOWMULTISELECT)?;
@@ -1,18 +1,35 @@
-use std::path::PathBuf;
+//! The FileChooser and Documents portals, called through zbus.
+//!
+//! ashpd's typed requests only return once the user answered, so there is no request left to
+//! close when the dialog future is dropped, and their `SelectedFiles` leaves out the filter the
+//! user selected. The options are built by hand instead, their D-Bus signatures are checked by
+//! the tests at the end of this file.
 
-use super::linux::zenity;
-use crate::backend::DialogFutureType;
-use crate::file_dialog::Filter;
-use crate::message_dialog::MessageDialog;
-use crate::{FileDialog, FileHandle, MessageButtons, MessageDialogResult};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
 
-use ashpd::desktop::file_chooser::{FileFilter, OpenFileRequest, SaveFileRequest};
+use super::linux::{DialogKind, Picked};
+use crate::file_dialog::{Choice, Filter};
+use crate::{Error, FileDialog};
+
+use ashpd::zbus::export::futures_util::StreamExt;
+use ashpd::zbus::proxy::SignalStream;
+use ashpd::zbus::zvariant::{Fd, OwnedObjectPath, OwnedValue, Value};
+use ashpd::zbus::{self, Connection, Proxy};
 use ashpd::WindowIdentifier;
 
-use log::error;
 use pollster::block_on;
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 
+const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PATH: &str = "/org/freedesktop/portal/desktop";
+const FILE_CHOOSER: &str = "org.freedesktop.portal.FileChooser";
+const REQUEST: &str = "org.freedesktop.portal.Request";
+
 fn to_window_identifier(
     window: Option<RawWindowHandle>,
     display: Option<RawDisplayHandle>,
@@ -24,296 +41,438 @@ fn to_window_identifier(
     })
 }
 
-impl From<&Filter> for FileFilter {
-    fn from(filter: &Filter) -> Self {
-        let mut ashpd_filter = FileFilter::new(&filter.name);
-        for file_extension in &filter.extensions {
-            if file_extension == "*" || file_extension == "" {
-                ashpd_filter = ashpd_filter.glob("*");
-            } else {
-                ashpd_filter = ashpd_filter.glob(&format!("*.{file_extension}"));
-            }
-        }
-        ashpd_filter
+fn portal_error(err: zbus::Error) -> Error {
+    Error::Portal(err.to_string())
+}
+
+/// The session bus connection, shared by every request.
+async fn connection() -> zbus::Result<Connection> {
+    static CONNECTION: OnceLock<Connection> = OnceLock::new();
+
+    if let Some(connection) = CONNECTION.get() {
+        return Ok(connection.clone());
     }
+
+    let connection = Connection::session().await?;
+    Ok(CONNECTION.get_or_init(|| connection).clone())
+}
+
+/// A `(name, [(type, pattern)])` portal filter, type 0 being a glob pattern and 1 a MIME type.
+type PortalFilter = (String, Vec<(u32, String)>);
+
+fn portal_filter(filter: &Filter) -> PortalFilter {
+    let patterns = filter
+        .to_globs(true)
+        .into_iter()
+        .map(|glob| (0, glob))
+        .chain(filter.mime_types().map(|mime| (1, mime.to_owned())))
+        .collect();
+
+    (filter.name.clone(), patterns)
 }
 
-//
-// File Picker
-//
+/// A `(id, label, [(option id, option label)], default)` portal choice, a checkbox when it has
+/// no options.
+type PortalChoice = (String, String, Vec<(String, String)>, String);
 
-use crate::backend::FilePickerDialogImpl;
-impl FilePickerDialogImpl for FileDialog {
-    fn pick_file(self) -> Option<PathBuf> {
-        block_on(self.pick_file_async()).map(PathBuf::from)
+fn portal_choice(choice: &Choice) -> PortalChoice {
+    (
+        choice.id.clone(),
+        choice.label.clone(),
+        choice.options.clone(),
+        choice.default.clone(),
+    )
+}
+
+/// Paths are sent to the portal as nul-terminated byte arrays.
+fn path_bytes(path: &Path) -> Vec<u8> {
+    let mut bytes = path.as_os_str().as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+fn portal_path(path: &Path) -> Value<'static> {
+    Value::from(path_bytes(path))
+}
+
+/// An `org.freedesktop.portal.Request`, closed if it is dropped before the user answered it.
+struct PortalRequest {
+    proxy: Proxy<'static>,
+    responses: SignalStream<'static>,
+    answered: bool,
+}
+
+impl PortalRequest {
+    async fn new(connection: &Connection, path: OwnedObjectPath) -> zbus::Result<Self> {
+        let proxy = Proxy::new_owned(connection.clone(), DESTINATION, path, REQUEST).await?;
+        // Listen before the request is made, the response could come before we subscribed.
+        let responses = proxy.receive_signal("Response").await?;
+
+        Ok(Self {
+            proxy,
+            responses,
+            answered: false,
+        })
     }
 
-    fn pick_files(self) -> Option<Vec<PathBuf>> {
-        block_on(self.pick_files_async())
-            .map(|vec_file_handle| vec_file_handle.iter().map(PathBuf::from).collect())
+    async fn response(mut self) -> Result<Option<HashMap<String, OwnedValue>>, Error> {
+        let message = self
+            .responses
+            .next()
+            .await
+            .ok_or_else(|| Error::Portal("the request ended without a response".into()))?;
+        self.answered = true;
+
+        let (code, results): (u32, HashMap<String, OwnedValue>) =
+            message.body().deserialize().map_err(portal_error)?;
+
+        match code {
+            0 => Ok(Some(results)),
+            1 => Ok(None),
+            _ => Err(Error::Portal("the request was ended by the portal".into())),
+        }
     }
 }
 
-use crate::backend::AsyncFilePickerDialogImpl;
-impl AsyncFilePickerDialogImpl for FileDialog {
-    fn pick_file_async(self) -> DialogFutureType<Option<FileHandle>> {
-        Box::pin(async move {
-            let res = OpenFileRequest::default()
-                .identifier(to_window_identifier(self.parent, self.parent_display))
-                .multiple(false)
-                .title(self.title.as_deref().or(None))
-                .filters(self.filters.iter().map(From::from))
-                .current_folder::<&PathBuf>(&self.starting_directory)
-                .expect("File path should not be nul-terminated")
-                .send()
-                .await;
-
-            if res.is_err() {
-                match zenity::pick_file(&self).await {
-                    Ok(res) => res,
-                    Err(err) => {
-                        error!("pick_file error {err}");
-                        return None;
-                    }
-                }
-            } else {
-                res.ok()
-                    .and_then(|request| request.response().ok())
-                    .and_then(|response| {
-                        response
-                            .uris()
-                            .first()
-                            .and_then(|uri| uri.to_file_path().ok())
-                    })
+impl Drop for PortalRequest {
+    fn drop(&mut self) {
+        if self.answered {
+            return;
+        }
+
+        // Drop can't wait for the call, and the caller may be in the middle of an executor.
+        let proxy = self.proxy.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = block_on(proxy.call_noreply("Close", &())) {
+                log::debug!("Failed to close the portal request: {err}");
             }
-            .map(FileHandle::from)
-        })
+        });
     }
+}
 
-    fn pick_files_async(self) -> DialogFutureType<Option<Vec<FileHandle>>> {
-        Box::pin(async move {
-            let res = OpenFileRequest::default()
-                .identifier(to_window_identifier(self.parent, self.parent_display))
-                .multiple(true)
-                .title(self.title.as_deref().or(None))
-                .filters(self.filters.iter().map(From::from))
-                .current_folder::<&PathBuf>(&self.starting_directory)
-                .expect("File path should not be nul-terminated")
-                .send()
-                .await;
-
-            if res.is_err() {
-                match zenity::pick_files(&self).await {
-                    Ok(res) => Some(res.into_iter().map(FileHandle::from).collect::<Vec<_>>()),
-                    Err(err) => {
-                        error!("pick_files error {err}");
-                        None
-                    }
-                }
-            } else {
-                res.ok()
-                    .and_then(|request| request.response().ok())
-                    .map(|response| {
-                        response
-                            .uris()
-                            .iter()
-                            .filter_map(|uri| uri.to_file_path().ok())
-                            .map(FileHandle::from)
-                            .collect::<Vec<FileHandle>>()
-                    })
-            }
-        })
+/// Calls `method` of the FileChooser portal and waits for the user to answer.
+///
+/// Dropping the returned future closes the dialog.
+async fn file_chooser_request(
+    method: &str,
+    parent: Option<WindowIdentifier>,
+    title: &str,
+    mut options: HashMap<&str, Value<'_>>,
+) -> Result<Option<HashMap<String, OwnedValue>>, Error> {
+    static NEXT_TOKEN: AtomicU64 = AtomicU64::new(0);
+
+    let connection = connection().await.map_err(portal_error)?;
+
+    let token = format!("rfd_{}", NEXT_TOKEN.fetch_add(1, Ordering::Relaxed));
+    let sender = connection
+        .unique_name()
+        .map(|name| name.trim_start_matches(':').replace('.', "_"))
+        .unwrap_or_default();
+    let path = format!("{PATH}/request/{sender}/{token}");
+    let path = OwnedObjectPath::try_from(path).map_err(|err| portal_error(err.into()))?;
+    options.insert("handle_token", Value::from(token));
+
+    let mut request = PortalRequest::new(&connection, path.clone())
+        .await
+        .map_err(portal_error)?;
+
+    let parent = parent.map(|parent| parent.to_string()).unwrap_or_default();
+    let chooser = Proxy::new(&connection, DESTINATION, PATH, FILE_CHOOSER)
+        .await
+        .map_err(portal_error)?;
+    let handle: OwnedObjectPath = chooser
+        .call(method, &(parent, title, options))
+        .await
+        .map_err(portal_error)?;
+
+    // Portals older than 0.9 ignore `handle_token` and pick their own path.
+    if handle != path {
+        request.answered = true;
+        request = PortalRequest::new(&connection, handle)
+            .await
+            .map_err(portal_error)?;
     }
+
+    request.response().await
 }
 
-//
-// Folder Picker
-//
+/// Turns the results of a FileChooser request into the selected paths, filter and choices.
+fn picked(mut results: HashMap<String, OwnedValue>) -> Result<Picked, Error> {
+    let uris = match results.remove("uris") {
+        Some(uris) => Vec::<String>::try_from(uris)
+            .map_err(|err| Error::Portal(format!("invalid uris in the response: {err}")))?,
+        None => Vec::new(),
+    };
+
+    let paths = uris
+        .iter()
+        .filter_map(|uri| ashpd::url::Url::parse(uri).ok())
+        .filter_map(|uri| uri.to_file_path().ok())
+        .collect();
 
-use crate::backend::FolderPickerDialogImpl;
-impl FolderPickerDialogImpl for FileDialog {
-    fn pick_folder(self) -> Option<PathBuf> {
-        block_on(self.pick_folder_async()).map(PathBuf::from)
+    let filter = results
+        .remove("current_filter")
+        .and_then(|filter| PortalFilter::try_from(filter).ok())
+        .map(|(name, _)| name);
+
+    let choices = results
+        .remove("choices")
+        .and_then(|choices| Vec::<(String, String)>::try_from(choices).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    Ok(Picked {
+        paths,
+        filter,
+        choices,
+    })
+}
+
+/// Checks once whether a FileChooser portal is running on the session bus.
+pub async fn is_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+    if let Some(available) = AVAILABLE.get() {
+        return *available;
     }
 
-    fn pick_folders(self) -> Option<Vec<PathBuf>> {
-        block_on(self.pick_folders_async())
-            .map(|vec_file_handle| vec_file_handle.iter().map(PathBuf::from).collect())
+    let probe = async {
+        let connection = connection().await?;
+        let proxy = Proxy::new(&connection, DESTINATION, PATH, FILE_CHOOSER).await?;
+        proxy.get_property::<u32>("version").await
+    };
+
+    let available = match probe.await {
+        Ok(_) => true,
+        Err(err) => {
+            log::debug!("XDG Desktop Portal FileChooser is not available: {err}");
+            false
+        }
+    };
+
+    *AVAILABLE.get_or_init(|| available)
+}
+
+/// The options shared by every FileChooser method.
+fn common_options(dialog: &FileDialog) -> HashMap<&'static str, Value<'static>> {
+    let mut options = HashMap::new();
+
+    let choices: Vec<_> = dialog.choices.iter().map(portal_choice).collect();
+    if !choices.is_empty() {
+        options.insert("choices", Value::from(choices));
+    }
+    if let Some(dir) = &dialog.starting_directory {
+        options.insert("current_folder", portal_path(dir));
+    }
+    if let Some(label) = &dialog.accept_label {
+        options.insert("accept_label", Value::from(label.clone()));
     }
+
+    options
 }
 
-use crate::backend::AsyncFolderPickerDialogImpl;
-impl AsyncFolderPickerDialogImpl for FileDialog {
-    fn pick_folder_async(self) -> DialogFutureType<Option<FileHandle>> {
-        Box::pin(async move {
-            let res = OpenFileRequest::default()
-                .identifier(to_window_identifier(self.parent, self.parent_display))
-                .multiple(false)
-                .directory(true)
-                .title(self.title.as_deref().or(None))
-                .filters(self.filters.iter().map(From::from))
-                .current_folder::<&PathBuf>(&self.starting_directory)
-                .expect("File path should not be nul-terminated")
-                .send()
-                .await;
-
-            if res.is_err() {
-                match zenity::pick_folder(&self).await {
-                    Ok(res) => res,
-                    Err(err) => {
-                        error!("pick_folder error {err}");
-                        return None;
-                    }
-                }
-            } else {
-                res.ok()
-                    .and_then(|request| request.response().ok())
-                    .and_then(|response| {
-                        response
-                            .uris()
-                            .first()
-                            .and_then(|uri| uri.to_file_path().ok())
-                    })
-            }
-            .map(FileHandle::from)
-        })
+/// The method of the FileChooser portal showing a `kind` dialog, and its options.
+fn file_dialog_options(
+    kind: DialogKind,
+    dialog: &FileDialog,
+) -> (&'static str, HashMap<&'static str, Value<'static>>) {
+    let mut options = common_options(dialog);
+
+    let filters: Vec<_> = dialog.filters.iter().map(portal_filter).collect();
+    if !filters.is_empty() {
+        options.insert("filters", Value::from(filters));
+    }
+    if let Some(filter) = dialog
+        .current_filter
+        .as_ref()
+        .and_then(|name| dialog.filters.iter().find(|filter| &filter.name == name))
+    {
+        options.insert("current_filter", Value::from(portal_filter(filter)));
     }
 
-    fn pick_folders_async(self) -> DialogFutureType<Option<Vec<FileHandle>>> {
-        Box::pin(async move {
-            let res = OpenFileRequest::default()
-                .identifier(to_window_identifier(self.parent, self.parent_display))
-                .multiple(true)
-                .directory(true)
-                .title(self.title.as_deref().or(None))
-                .filters(self.filters.iter().map(From::from))
-                .current_folder::<&PathBuf>(&self.starting_directory)
-                .expect("File path should not be nul-terminated")
-                .send()
-                .await;
-
-            if res.is_err() {
-                match zenity::pick_folders(&self).await {
-                    Ok(res) => Some(res.into_iter().map(FileHandle::from).collect::<Vec<_>>()),
-                    Err(err) => {
-                        error!("pick_files error {err}");
-                        None
-                    }
-                }
-            } else {
-                res.ok()
-                    .and_then(|request| request.response().ok())
-                    .map(|response| {
-                        response
-                            .uris()
-                            .iter()
-                            .filter_map(|uri| uri.to_file_path().ok())
-                            .map(FileHandle::from)
-                            .collect::<Vec<FileHandle>>()
-                    })
+    let method = match kind {
+        DialogKind::SaveFile => {
+            if let Some(name) = &dialog.file_name {
+                options.insert(
+                    "current_name",
+                    Value::from(name.to_string_lossy().into_owned()),
+                );
             }
-        })
-    }
+            "SaveFile"
+        }
+        _ => {
+            options.insert("multiple", Value::from(kind.is_multiple()));
+            options.insert("directory", Value::from(kind.is_folder()));
+            "OpenFile"
+        }
+    };
+
+    (method, options)
 }
 
-//
-// File Save
-//
+/// Shows a file chooser through the XDG Desktop Portal.
+pub async fn file_dialog(kind: DialogKind, dialog: &FileDialog) -> Result<Option<Picked>, Error> {
+    let (method, options) = file_dialog_options(kind, dialog);
+
+    let parent = to_window_identifier(dialog.parent, dialog.parent_display);
+    let title = dialog.title.as_deref().unwrap_or_default();
 
-use crate::backend::FileSaveDialogImpl;
-impl FileSaveDialogImpl for FileDialog {
-    fn save_file(self) -> Option<PathBuf> {
-        block_on(self.save_file_async()).map(PathBuf::from)
+    match file_chooser_request(method, parent, title, options).await? {
+        Some(results) => picked(results).map(Some),
+        None => Ok(None),
     }
 }
 
-use crate::backend::AsyncFileSaveDialogImpl;
-impl AsyncFileSaveDialogImpl for FileDialog {
-    fn save_file_async(self) -> DialogFutureType<Option<FileHandle>> {
-        Box::pin(async move {
-            let res = SaveFileRequest::default()
-                .identifier(to_window_identifier(self.parent, self.parent_display))
-                .title(self.title.as_deref().or(None))
-                .current_name(self.file_name.as_deref())
-                .filters(self.filters.iter().map(From::from))
-                .current_folder::<&PathBuf>(&self.starting_directory)
-                .expect("File path should not be nul-terminated")
-                .send()
-                .await;
-
-            if res.is_err() {
-                match zenity::save_file(&self).await {
-                    Ok(res) => res,
-                    Err(err) => {
-                        error!("pick_folder error {err}");
-                        return None;
-                    }
-                }
-            } else {
-                res.ok()
-                    .and_then(|request| request.response().ok())
-                    .and_then(|response| {
-                        response
-                            .uris()
-                            .first()
-                            .and_then(|uri| uri.to_file_path().ok())
-                    })
-            }
-            .map(FileHandle::from)
-        })
+/// The options of the `SaveFiles` method.
+fn save_files_options(
+    dialog: &FileDialog,
+    names: &[String],
+) -> HashMap<&'static str, Value<'static>> {
+    let mut options = common_options(dialog);
+    let files: Vec<_> = names
+        .iter()
+        .map(|name| path_bytes(Path::new(name)))
+        .collect();
+    options.insert("files", Value::from(files));
+    options
+}
+
+/// Asks for a folder to save the files named `names` in, through the `SaveFiles` method.
+pub async fn save_files(dialog: &FileDialog, names: &[String]) -> Result<Option<Picked>, Error> {
+    let options = save_files_options(dialog, names);
+
+    let parent = to_window_identifier(dialog.parent, dialog.parent_display);
+    let title = dialog.title.as_deref().unwrap_or_default();
+
+    match file_chooser_request("SaveFiles", parent, title, options).await? {
+        Some(results) => picked(results).map(Some),
+        None => Ok(None),
     }
 }
 
-use crate::backend::MessageDialogImpl;
-impl MessageDialogImpl for MessageDialog {
-    fn show(self) -> MessageDialogResult {
-        block_on(self.show_async())
+const DOCUMENTS_DESTINATION: &str = "org.freedesktop.portal.Documents";
+const DOCUMENTS_PATH: &str = "/org/freedesktop/portal/documents";
+const DOCUMENTS: &str = "org.freedesktop.portal.Documents";
+
+async fn documents() -> Result<Proxy<'static>, Error> {
+    let connection = connection().await.map_err(portal_error)?;
+    Proxy::new_owned(connection, DOCUMENTS_DESTINATION, DOCUMENTS_PATH, DOCUMENTS)
+        .await
+        .map_err(portal_error)
+}
+
+/// The path of the document `id` outside of the sandbox, `None` if the document portal does not
+/// tell, which is the case before version 5 of its interface.
+pub async fn host_path(id: &str) -> Result<Option<PathBuf>, Error> {
+    let documents = documents().await?;
+    let version = documents
+        .get_property::<u32>("version")
+        .await
+        .map_err(portal_error)?;
+    if version < 5 {
+        return Ok(None);
     }
+
+    let mut paths: HashMap<String, Vec<u8>> = documents
+        .call("GetHostPaths", &(vec![id],))
+        .await
+        .map_err(portal_error)?;
+
+    Ok(paths.remove(id).map(|mut bytes| {
+        // Paths are nul-terminated byte arrays.
+        if bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        PathBuf::from(OsString::from_vec(bytes))
+    }))
 }
 
-use crate::backend::AsyncMessageDialogImpl;
-impl AsyncMessageDialogImpl for MessageDialog {
-    fn show_async(self) -> DialogFutureType<MessageDialogResult> {
-        Box::pin(async move {
-            match &self.buttons {
-                MessageButtons::Ok | MessageButtons::OkCustom(_) => {
-                    let res = crate::backend::linux::zenity::message(
-                        &self.level,
-                        &self.buttons,
-                        &self.title,
-                        &self.description,
-                    )
-                    .await;
-
-                    match res {
-                        Ok(res) => res,
-                        Err(err) => {
-                            log::error!("Failed to open zenity dialog: {err}");
-                            MessageDialogResult::Cancel
-                        }
-                    }
-                }
-                MessageButtons::OkCancel
-                | MessageButtons::YesNo
-                | MessageButtons::OkCancelCustom(..)
-                | MessageButtons::YesNoCancel
-                | MessageButtons::YesNoCancelCustom(..) => {
-                    let res = crate::backend::linux::zenity::question(
-                        &self.buttons,
-                        &self.title,
-                        &self.description,
-                    )
-                    .await;
-
-                    match res {
-                        Ok(res) => res,
-                        Err(err) => {
-                            log::error!("Failed to open zenity dialog: {err}");
-                            MessageDialogResult::Cancel
-                        }
-                    }
-                }
-            }
-        })
+/// Adds `path` to the document portal for good, so the sandboxed application keeps access to
+/// it after a restart. Returns the ID of the document.
+pub async fn add_persistent(path: &Path) -> Result<String, Error> {
+    // The portal wants a file descriptor, proving that the application has access to the file.
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(target_os = "linux")]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, libc::O_PATH);
+    let file = options.open(path)?;
+
+    let reuse_existing = true;
+    let persistent = true;
+    documents()
+        .await?
+        .call("Add", &(Fd::from(&file), reuse_existing, persistent))
+        .await
+        .map_err(portal_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The signatures of the options in the FileChooser portal documentation.
+    const SIGNATURES: &[(&str, &str)] = &[
+        ("handle_token", "s"),
+        ("accept_label", "s"),
+        ("modal", "b"),
+        ("multiple", "b"),
+        ("directory", "b"),
+        ("filters", "a(sa(us))"),
+        ("current_filter", "(sa(us))"),
+        ("choices", "a(ssa(ss)s)"),
+        ("current_name", "s"),
+        ("current_folder", "ay"),
+        ("current_file", "ay"),
+        ("files", "aay"),
+    ];
+
+    fn assert_signatures(options: &HashMap<&str, Value<'_>>, keys: &[&str]) {
+        let mut names: Vec<_> = options.keys().copied().collect();
+        names.sort_unstable();
+        let mut expected = keys.to_vec();
+        expected.sort_unstable();
+        assert_eq!(names, expected);
+
+        for (key, value) in options {
+            let (_, signature) = SIGNATURES.iter().find(|(name, _)| name == key).unwrap();
+            assert_eq!(value.value_signature(), *signature, "{key}");
+        }
+    }
+
+    fn dialog() -> FileDialog {
+        FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .add_mime_filter("Images", &["image/*"])
+            .set_current_filter("Images")
+            .add_choice("enc", "Encoding", &[("utf8", "UTF-8")], "utf8")
+            .add_checkbox("hidden", "Hidden files", false)
+            .set_directory("/tmp")
+            .set_file_name("notes.txt")
+            .set_accept_label("Go")
+    }
+
+    #[test]
+    fn options_signatures() {
+        let common = ["choices", "current_folder", "accept_label"];
+        let filters = ["filters", "current_filter"];
+
+        let (method, options) = file_dialog_options(DialogKind::PickFiles, &dialog());
+        assert_eq!(method, "OpenFile");
+        assert_signatures(
+            &options,
+            &[&common[..], &filters, &["multiple", "directory"]].concat(),
+        );
+
+        let (method, options) = file_dialog_options(DialogKind::SaveFile, &dialog());
+        assert_eq!(method, "SaveFile");
+        assert_signatures(
+            &options,
+            &[&common[..], &filters, &["current_name"]].concat(),
+        );
+
+        let names = ["a.png".to_owned(), "b.png".to_owned()];
+        let options = save_files_options(&dialog(), &names);
+        assert_signatures(&options, &[&common[..], &["files"]].concat());
     }
 }
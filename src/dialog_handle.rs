@@ -0,0 +1,222 @@
+use crate::backend::DialogFutureType;
+use crate::Error;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct HandleState {
+    closed: bool,
+    wakers: Vec<Waker>,
+}
+
+/// Dismisses async dialogs from outside of the future that shows them.
+///
+/// Attach it to one or more dialogs with [`AsyncFileDialog::with_handle`](crate::AsyncFileDialog::with_handle)
+/// or [`AsyncMessageDialog::with_handle`](crate::AsyncMessageDialog::with_handle), then call
+/// [`DialogHandle::close`] when the dialogs are no longer relevant, eg. because the document
+/// they were opened for was closed. Their futures resolve as if the user cancelled them.
+///
+/// ```no_run
+/// # async fn f() {
+/// let handle = rfd::DialogHandle::new();
+/// let file = rfd::AsyncFileDialog::new().with_handle(&handle).pick_file();
+///
+/// // Somewhere else
+/// handle.close();
+///
+/// assert!(file.await.is_none());
+/// # }
+/// ```
+///
+/// Simply dropping the future of an async dialog also dismisses it, the handle is for when the
+/// future is owned by someone else.
+#[derive(Debug, Clone, Default)]
+pub struct DialogHandle {
+    state: Arc<Mutex<HandleState>>,
+}
+
+impl DialogHandle {
+    /// A handle with no dialog attached to it yet.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Dismisses every dialog attached to this handle, including the ones attached later.
+    pub fn close(&self) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            std::mem::take(&mut state.wakers)
+        };
+
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Whether [`DialogHandle::close`] was called.
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// Makes `future` resolve to `Ok(closed())` as soon as `handle` is closed, and to
+    /// [`Error::TimedOut`] once `timeout` expired.
+    pub(crate) fn attach<T: 'static>(
+        handle: Option<DialogHandle>,
+        timeout: Option<Duration>,
+        future: DialogFutureType<Result<T, Error>>,
+        closed: fn() -> T,
+    ) -> DialogFutureType<Result<T, Error>> {
+        if handle.is_none() && timeout.is_none() {
+            return future;
+        }
+
+        Box::pin(Closable {
+            future: Some(future),
+            handle: handle.map(|handle| (handle, closed)),
+            timer: timeout.and_then(Timer::new),
+        })
+    }
+
+    /// Makes `future` resolve to [`Error::TimedOut`] once `timeout` expired.
+    #[cfg_attr(
+        not(any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn with_timeout<T: 'static>(
+        timeout: Option<Duration>,
+        future: DialogFutureType<Result<T, Error>>,
+    ) -> DialogFutureType<Result<T, Error>> {
+        Self::attach(None, timeout, future, || unreachable!("no handle to close"))
+    }
+
+    /// Registers `waker` to be woken up once the handle is closed, returns whether it is.
+    fn poll_closed(&self, waker: &Waker) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.closed && !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+        state.closed
+    }
+}
+
+/// A handle closed once a timeout expired.
+///
+/// The thread waiting for the timeout stops as soon as the timer is dropped, which the dialog
+/// future does when it resolves or is dropped.
+struct Timer {
+    handle: DialogHandle,
+    _stop: mpsc::Sender<()>,
+}
+
+impl Timer {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn new(timeout: Duration) -> Option<Self> {
+        let handle = DialogHandle::new();
+        let state = Arc::downgrade(&handle.state);
+        let (stop, stopped) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                if let Some(state) = state.upgrade() {
+                    DialogHandle { state }.close();
+                }
+            }
+        });
+
+        Some(Self {
+            handle,
+            _stop: stop,
+        })
+    }
+
+    /// There are no threads to run a timer on.
+    #[cfg(target_arch = "wasm32")]
+    fn new(_timeout: Duration) -> Option<Self> {
+        None
+    }
+}
+
+/// A dialog future that can be dismissed through a [`DialogHandle`] or a timeout.
+struct Closable<T> {
+    future: Option<DialogFutureType<Result<T, Error>>>,
+    handle: Option<(DialogHandle, fn() -> T)>,
+    timer: Option<Timer>,
+}
+
+impl<T> Future for Closable<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = if let Some((_, closed)) = self
+            .handle
+            .as_ref()
+            .filter(|(handle, _)| handle.poll_closed(cx.waker()))
+        {
+            Ok(closed())
+        } else if let Some(true) = self
+            .timer
+            .as_ref()
+            .map(|timer| timer.handle.poll_closed(cx.waker()))
+        {
+            Err(Error::TimedOut)
+        } else if let Some(future) = self.future.as_mut() {
+            match future.as_mut().poll(cx) {
+                Poll::Ready(res) => res,
+                Poll::Pending => return Poll::Pending,
+            }
+        } else {
+            return Poll::Pending;
+        };
+
+        // Dropping the dialog future is what dismisses the dialog, and dropping the timer stops
+        // its thread.
+        self.future = None;
+        self.timer = None;
+        Poll::Ready(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close() {
+        let handle = DialogHandle::new();
+        let pending: DialogFutureType<Result<Option<u32>, Error>> =
+            Box::pin(std::future::pending());
+        let future = DialogHandle::attach(Some(handle.clone()), None, pending, || None);
+
+        let closer = std::thread::spawn(move || handle.close());
+        assert!(matches!(futures::executor::block_on(future), Ok(None)));
+        closer.join().unwrap();
+    }
+
+    #[test]
+    fn timeout() {
+        let pending: DialogFutureType<Result<u32, Error>> = Box::pin(std::future::pending());
+        let future = DialogHandle::with_timeout(Some(Duration::from_millis(10)), pending);
+
+        assert!(matches!(
+            futures::executor::block_on(future),
+            Err(Error::TimedOut)
+        ));
+    }
+
+    #[test]
+    fn answered_before_timeout() {
+        let answered: DialogFutureType<Result<u32, Error>> = Box::pin(async { Ok(1) });
+        let future = DialogHandle::with_timeout(Some(Duration::from_secs(3600)), answered);
+
+        assert!(matches!(futures::executor::block_on(future), Ok(1)));
+    }
+}
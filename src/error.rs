@@ -0,0 +1,84 @@
+use std::fmt::{Display, Formatter};
+
+/// Error returned by the fallible (`try_*`) dialog functions.
+///
+/// A dialog that was dismissed by the user is not an error, it is reported as `Ok(None)`
+/// (or [`MessageDialogResult::Cancel`](crate::MessageDialogResult::Cancel) for message dialogs).
+/// This type describes the cases where no dialog could be shown at all, or where the dialog
+/// backend failed before the user could answer.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The XDG Desktop Portal D-Bus call failed.
+    Portal(String),
+    /// A helper binary (eg. `zenity`) needed to show the dialog is not installed.
+    HelperNotFound(String),
+    /// A helper binary exited abnormally.
+    HelperFailed {
+        /// Name of the helper binary.
+        program: String,
+        /// Exit code of the helper, `None` if it was killed by a signal.
+        code: Option<i32>,
+        /// What the helper printed to its standard error.
+        stderr: String,
+    },
+    /// A helper binary printed something that is not valid UTF-8.
+    InvalidUtf8(std::string::FromUtf8Error),
+    /// There is no display server to show the dialog on.
+    NoDisplay,
+    /// None of the dialog backends allowed by the Linux fallback policy is available.
+    NoBackend,
+    /// An I/O error occurred while talking to the dialog backend.
+    Io(std::io::Error),
+    /// The user did not answer the dialog before the timeout set with `set_timeout` expired.
+    TimedOut,
+    /// The `RFD_AUTOMATION` answer file is invalid, or has no answer for this dialog.
+    Automation(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidUtf8(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Portal(err) => write!(f, "XDG Desktop Portal error: {err}"),
+            Error::HelperNotFound(program) => write!(f, "`{program}` is not installed"),
+            Error::HelperFailed {
+                program,
+                code: Some(code),
+                stderr,
+            } => write!(f, "`{program}` exited with code {code}: {stderr}"),
+            Error::HelperFailed {
+                program,
+                code: None,
+                stderr,
+            } => write!(f, "`{program}` was terminated by a signal: {stderr}"),
+            Error::InvalidUtf8(err) => write!(f, "invalid UTF-8 in dialog output: {err}"),
+            Error::NoDisplay => write!(f, "no display available to show the dialog on"),
+            Error::NoBackend => write!(f, "no dialog backend is available"),
+            Error::Io(err) => err.fmt(f),
+            Error::TimedOut => write!(f, "the dialog timed out"),
+            Error::Automation(err) => write!(f, "RFD_AUTOMATION: {err}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(value: std::string::FromUtf8Error) -> Self {
+        Self::InvalidUtf8(value)
+    }
+}
@@ -1,15 +1,351 @@
-use crate::FileHandle;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::custom::{CustomBackend, DialogBackend};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::persistence::{Persistence, Selection};
+use crate::{DialogFutureType, Error, FileHandle};
 
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
 use std::path::Path;
 use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use std::time::Duration;
 
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle};
 
+/// A pattern of a [`Filter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FilterPattern {
+    /// Files with this extension, in any case, eg. `png` also matches `photo.PNG`
+    Extension(String),
+    /// Files whose name matches this glob, eg. `Makefile` or `*.tar.*`.
+    /// Matching is case-sensitive on Linux.
+    Glob(String),
+    /// Files of this MIME type, eg. `application/pdf` or `image/*`
+    MimeType(String),
+}
+
+impl FilterPattern {
+    /// Files with the extension `extension`, leading dots are ignored.
+    pub fn extension(extension: impl Into<String>) -> Self {
+        Self::Extension(extension.into()).normalized()
+    }
+
+    /// Files whose name matches `glob`
+    pub fn glob(glob: impl Into<String>) -> Self {
+        Self::Glob(glob.into())
+    }
+
+    /// Files of the MIME type `mime_type`
+    pub fn mime_type(mime_type: impl Into<String>) -> Self {
+        Self::MimeType(mime_type.into())
+    }
+
+    /// Parses the extensions given to [`FileDialog::add_filter`], which historically also
+    /// accept things like `*`, `.png` or `*.png`.
+    fn from_extension(extension: &str) -> Self {
+        let extension = extension
+            .strip_prefix("*.")
+            .unwrap_or_else(|| extension.trim_start_matches('.'));
+
+        if extension.is_empty() || extension == "*" {
+            Self::Glob("*".to_owned())
+        } else if extension.contains(['*', '?', '[']) {
+            Self::Glob(format!("*.{extension}"))
+        } else {
+            Self::Extension(extension.to_owned())
+        }
+    }
+
+    fn normalized(self) -> Self {
+        match self {
+            Self::Extension(extension) => Self::from_extension(&extension),
+            pattern => pattern,
+        }
+    }
+}
+
+/// File filter added with [`FileDialog::add_filter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub(crate) name: String,
+    pub(crate) patterns: Vec<FilterPattern>,
+}
+
+impl Filter {
+    /// Name of the filter, as displayed to the user
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Patterns matched by the filter
+    pub fn patterns(&self) -> &[FilterPattern] {
+        &self.patterns
+    }
+
+    /// Extensions matched by the filter, without the leading dot
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().filter_map(|pattern| match pattern {
+            FilterPattern::Extension(extension) => Some(extension.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Globs matched by the filter, see [`FilterPattern::Glob`]
+    pub fn globs(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().filter_map(|pattern| match pattern {
+            FilterPattern::Glob(glob) => Some(glob.as_str()),
+            _ => None,
+        })
+    }
+
+    /// MIME types matched by the filter
+    pub fn mime_types(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().filter_map(|pattern| match pattern {
+            FilterPattern::MimeType(mime_type) => Some(mime_type.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The extensions and globs of the filter as globs, for the backends that only take globs.
+    ///
+    /// With `case_insensitive`, extensions become globs like `*.[pP][nN][gG]`, for the
+    /// backends that match globs case-sensitively.
+    #[cfg_attr(any(target_os = "macos", target_arch = "wasm32"), allow(dead_code))]
+    pub(crate) fn to_globs(&self, case_insensitive: bool) -> Vec<String> {
+        self.patterns
+            .iter()
+            .filter_map(|pattern| match pattern {
+                FilterPattern::Extension(extension) if case_insensitive => {
+                    let mut glob = String::from("*.");
+                    for c in extension.chars() {
+                        let (lower, upper) = (c.to_lowercase(), c.to_uppercase());
+                        if lower.clone().eq(upper.clone()) {
+                            glob.push(c);
+                        } else {
+                            glob.push('[');
+                            glob.extend(lower.chain(upper));
+                            glob.push(']');
+                        }
+                    }
+                    Some(glob)
+                }
+                FilterPattern::Extension(extension) => Some(format!("*.{extension}")),
+                FilterPattern::Glob(glob) => Some(glob.clone()),
+                FilterPattern::MimeType(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl Filter {
+    /// Whether the name of `path` matches one of the patterns of the filter.
+    ///
+    /// MIME types are matched through the file name patterns of the shared MIME-info database,
+    /// so only on Linux & BSDs.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        let name = name.to_string_lossy();
+
+        self.patterns.iter().any(|pattern| match pattern {
+            FilterPattern::Extension(extension) => name
+                .to_lowercase()
+                .ends_with(&format!(".{}", extension.to_lowercase())),
+            FilterPattern::Glob(glob) => glob_matches(
+                glob,
+                &name,
+                cfg!(any(target_os = "windows", target_os = "macos")),
+            ),
+            #[cfg(any(
+                target_os = "linux",
+                target_os = "freebsd",
+                target_os = "dragonfly",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            ))]
+            FilterPattern::MimeType(mime_type) => crate::backend::linux::mime::globs(mime_type)
+                .any(|glob| glob_matches(glob, &name, true)),
+            #[cfg(not(any(
+                target_os = "linux",
+                target_os = "freebsd",
+                target_os = "dragonfly",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            )))]
+            FilterPattern::MimeType(_) => false,
+        })
+    }
+}
+
+/// Whether `name` matches the shell pattern `glob`, made of `*`, `?` and `[...]` classes.
+fn glob_matches(glob: &str, name: &str, case_insensitive: bool) -> bool {
+    let chars = |s: &str| -> Vec<char> {
+        if case_insensitive {
+            s.to_lowercase().chars().collect()
+        } else {
+            s.chars().collect()
+        }
+    };
+    let (glob, name) = (chars(glob), chars(name));
+
+    // Where to go back to when the rest does not match: after the last `*`, which then takes
+    // one more character of the name.
+    let mut star = None;
+    let (mut g, mut n) = (0, 0);
+    while n < name.len() {
+        let next = match glob.get(g) {
+            Some('*') => {
+                g += 1;
+                star = Some((g, n));
+                continue;
+            }
+            Some('?') => Some(g + 1),
+            Some('[') => class_matches(&glob[g..], name[n]).map(|len| g + len),
+            Some(&c) => (c == name[n]).then_some(g + 1),
+            None => None,
+        };
+
+        match (next, star) {
+            (Some(next), _) => {
+                g = next;
+                n += 1;
+            }
+            (None, Some((after_star, start))) => {
+                star = Some((after_star, start + 1));
+                g = after_star;
+                n = start + 1;
+            }
+            (None, None) => return false,
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// Matches `c` with the `[...]` class `glob` starts with, returns the length of the class if it
+/// matches. A `[` that is not closed is an ordinary character.
+fn class_matches(glob: &[char], c: char) -> Option<usize> {
+    let negated = matches!(glob.get(1), Some('!' | '^'));
+    let start = if negated { 2 } else { 1 };
+    // A `]` right at the start is part of the class.
+    let Some(end) = glob
+        .iter()
+        .skip(start + 1)
+        .position(|&c| c == ']')
+        .map(|i| i + start + 1)
+    else {
+        return (c == '[').then_some(1);
+    };
+
+    let class = &glob[start..end];
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        if class.get(i + 1) == Some(&'-') && i + 2 < class.len() {
+            matched |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    (matched != negated).then_some(end + 1)
+}
+
+/// An extra option shown in a file dialog, added with [`FileDialog::add_choice`] or
+/// [`FileDialog::add_checkbox`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice {
+    pub(crate) id: String,
+    pub(crate) label: String,
+    pub(crate) options: Vec<(String, String)>,
+    pub(crate) default: String,
+}
+
+impl Choice {
+    /// Identifier of the choice, the key of its value in [`FileResponse::choices`]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Label of the choice, as displayed to the user
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The options as `(id, label)` pairs, empty for a checkbox
+    pub fn options(&self) -> &[(String, String)] {
+        &self.options
+    }
+
+    /// Id of the option selected by default, `"true"` or `"false"` for a checkbox
+    pub fn default(&self) -> &str {
+        &self.default
+    }
+
+    /// Whether the choice is a checkbox
+    pub fn is_checkbox(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+/// The answer to a file dialog, along with what the user selected besides the files,
+/// see [`FileDialog::pick_file_with_response`]
 #[derive(Debug, Clone)]
-pub(crate) struct Filter {
-    #[allow(dead_code)]
-    pub name: String,
-    pub extensions: Vec<String>,
+#[non_exhaustive]
+pub struct FileResponse<T> {
+    /// The selected files, `PathBuf`s for [`FileDialog`] and [`FileHandle`]s for [`AsyncFileDialog`]
+    pub selection: T,
+    /// The filter that was selected when the user confirmed the dialog,
+    /// `None` if there is none or the backend does not tell
+    pub selected_filter: Option<Filter>,
+    /// The id of the option selected for each [`Choice`], by choice id.
+    /// Backends that can't show choices answer with their defaults.
+    pub choices: HashMap<String, String>,
+}
+
+impl<T> FileResponse<T> {
+    pub(crate) fn new(selection: T) -> Self {
+        Self {
+            selection,
+            selected_filter: None,
+            choices: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn map<U>(self, f: impl FnOnce(T) -> U) -> FileResponse<U> {
+        FileResponse {
+            selection: f(self.selection),
+            selected_filter: self.selected_filter,
+            choices: self.choices,
+        }
+    }
+
+    /// The id of the option selected for the choice `id`
+    pub fn choice(&self, id: &str) -> Option<&str> {
+        self.choices.get(id).map(String::as_str)
+    }
+
+    /// Whether the checkbox `id` was checked
+    pub fn checkbox(&self, id: &str) -> bool {
+        self.choice(id) == Some("true")
+    }
+
+    /// Answers the choices the backend did not with their defaults.
+    fn with_defaults(mut self, choices: &[Choice]) -> Self {
+        for choice in choices {
+            self.choices
+                .entry(choice.id.clone())
+                .or_insert_with(|| choice.default.clone());
+        }
+        self
+    }
 }
 
 /// Synchronous File Dialog. Supported platforms:
@@ -19,12 +355,23 @@ pub(crate) struct Filter {
 #[derive(Default, Debug, Clone)]
 pub struct FileDialog {
     pub(crate) filters: Vec<Filter>,
+    pub(crate) current_filter: Option<String>,
     pub(crate) starting_directory: Option<PathBuf>,
-    pub(crate) file_name: Option<String>,
+    pub(crate) file_name: Option<OsString>,
     pub(crate) title: Option<String>,
+    pub(crate) accept_label: Option<String>,
     pub(crate) parent: Option<RawWindowHandle>,
     pub(crate) parent_display: Option<RawDisplayHandle>,
     pub(crate) can_create_directories: Option<bool>,
+    pub(crate) add_to_recent: bool,
+    pub(crate) auto_extension: bool,
+    pub(crate) choices: Vec<Choice>,
+    pub(crate) timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) backend: Option<CustomBackend>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) persistence_key: Option<String>,
+    pub(crate) handle: Option<DialogHandle>,
 }
 
 // Oh god, I don't like sending RawWindowHandle between threads but here we go anyways...
@@ -51,18 +398,82 @@ impl FileDialog {
     pub fn add_filter(mut self, name: impl Into<String>, extensions: &[impl ToString]) -> Self {
         self.filters.push(Filter {
             name: name.into(),
-            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            patterns: extensions
+                .iter()
+                .map(|e| FilterPattern::from_extension(&e.to_string()))
+                .collect(),
         });
         self
     }
 
+    /// Add MIME type filter.
+    ///
+    /// Takes in the name of the filter, and list of MIME types, like `image/*` or `application/pdf`
+    ///
+    /// Supported platforms:
+    ///   * Linux (zenity uses the file name patterns of the shared MIME-info database)
+    ///   * WASM32
+    ///
+    /// Other platforms ignore the MIME types of a filter.
+    pub fn add_mime_filter(
+        mut self,
+        name: impl Into<String>,
+        mime_types: &[impl ToString],
+    ) -> Self {
+        self.filters.push(Filter {
+            name: name.into(),
+            patterns: mime_types
+                .iter()
+                .map(|m| FilterPattern::MimeType(m.to_string()))
+                .collect(),
+        });
+        self
+    }
+
+    /// Add a filter made of any [`FilterPattern`]s.
+    ///
+    /// ```no_run
+    /// use rfd::FilterPattern;
+    ///
+    /// let dialog = rfd::FileDialog::new().add_pattern_filter(
+    ///     "Build files",
+    ///     [FilterPattern::glob("Makefile"), FilterPattern::extension("mk")],
+    /// );
+    /// ```
+    ///
+    /// Globs and MIME types are not supported on every platform, see [`FileDialog::add_mime_filter`].
+    /// Mac and WASM32 ignore globs.
+    pub fn add_pattern_filter(
+        mut self,
+        name: impl Into<String>,
+        patterns: impl IntoIterator<Item = FilterPattern>,
+    ) -> Self {
+        self.filters.push(Filter {
+            name: name.into(),
+            patterns: patterns
+                .into_iter()
+                .map(FilterPattern::normalized)
+                .collect(),
+        });
+        self
+    }
+
+    /// Select the filter named `name` when the dialog opens, instead of the first one.
+    /// Supported platforms:
+    ///  * Linux
+    ///  * Windows
+    pub fn set_current_filter(mut self, name: impl Into<String>) -> Self {
+        self.current_filter = Some(name.into());
+        self
+    }
+
     /// Set starting directory of the dialog. Supported platforms:
-    ///   * Linux ([GTK only](https://github.com/PolyMeilex/rfd/issues/42))
+    ///   * Linux
     ///   * Windows
     ///   * Mac
     pub fn set_directory<P: AsRef<Path>>(mut self, path: P) -> Self {
         let path = path.as_ref();
-        if path.to_str().map(|p| p.is_empty()).unwrap_or(false) {
+        if path.as_os_str().is_empty() {
             self.starting_directory = None;
         } else {
             self.starting_directory = Some(path.into());
@@ -74,7 +485,7 @@ impl FileDialog {
     ///  * Windows
     ///  * Linux
     ///  * Mac
-    pub fn set_file_name(mut self, file_name: impl Into<String>) -> Self {
+    pub fn set_file_name(mut self, file_name: impl Into<OsString>) -> Self {
         self.file_name = Some(file_name.into());
         self
     }
@@ -88,11 +499,21 @@ impl FileDialog {
         self
     }
 
+    /// Set the label of the button that accepts the dialog, eg. "Import" instead of "Open".
+    /// Supported platforms:
+    ///  * Linux
+    ///  * Windows
+    ///  * Mac
+    pub fn set_accept_label(mut self, label: impl Into<String>) -> Self {
+        self.accept_label = Some(label.into());
+        self
+    }
+
     /// Set parent windows explicitly (optional).
     /// Supported platforms:
     ///  * Windows
     ///  * Mac
-    ///  * Linux (XDG only)
+    ///  * Linux (XDG Portal, and zenity on X11)
     pub fn set_parent<W: HasWindowHandle + HasDisplayHandle>(mut self, parent: &W) -> Self {
         self.parent = parent.window_handle().ok().map(|x| x.as_raw());
         self.parent_display = parent.display_handle().ok().map(|x| x.as_raw());
@@ -105,31 +526,291 @@ impl FileDialog {
         self.can_create_directories.replace(can);
         self
     }
+
+    /// Add the picked or saved files to the desktop's recently used files, see [`rfd::recent`](crate::recent).
+    /// Supported platforms:
+    ///  * Linux
+    pub fn set_add_to_recent(mut self, add: bool) -> Self {
+        self.add_to_recent = add;
+        self
+    }
+
+    /// Append the first extension of the selected filter to the name of a saved file,
+    /// unless it already ends with one of the extensions of that filter.
+    /// Supported platforms:
+    ///  * Linux (zenity does not tell which filter was selected, the first one is assumed)
+    ///
+    /// Windows and Mac always do it.
+    pub fn set_auto_extension(mut self, auto: bool) -> Self {
+        self.auto_extension = auto;
+        self
+    }
+
+    /// Add a combo box of `options`, given as `(id, label)` pairs, under the files.
+    ///
+    /// The id of the selected option is returned in [`FileResponse::choices`], under `id`.
+    /// Supported platforms:
+    ///  * Linux (zenity excepted)
+    ///
+    /// Other platforms answer with `default`.
+    pub fn add_choice(
+        mut self,
+        id: impl Into<String>,
+        label: impl Into<String>,
+        options: &[(impl ToString, impl ToString)],
+        default: impl Into<String>,
+    ) -> Self {
+        self.choices.push(Choice {
+            id: id.into(),
+            label: label.into(),
+            options: options
+                .iter()
+                .map(|(id, label)| (id.to_string(), label.to_string()))
+                .collect(),
+            default: default.into(),
+        });
+        self
+    }
+
+    /// Add a checkbox under the files, see [`FileDialog::add_choice`].
+    ///
+    /// It is answered with `"true"` or `"false"`, see [`FileResponse::checkbox`].
+    pub fn add_checkbox(
+        mut self,
+        id: impl Into<String>,
+        label: impl Into<String>,
+        default: bool,
+    ) -> Self {
+        self.choices.push(Choice {
+            id: id.into(),
+            label: label.into(),
+            options: Vec::new(),
+            default: default.to_string(),
+        });
+        self
+    }
+
+    /// Dismiss the dialog if the user did not answer it within `timeout`.
+    ///
+    /// The `try_*` functions then return [`Error::TimedOut`], the other ones behave as if the
+    /// dialog was cancelled. Supported platforms:
+    ///  * Linux
+    ///
+    /// Other platforms ignore the timeout of synchronous dialogs.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Show this dialog with a custom [`DialogBackend`] instead of the native one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_backend(mut self, backend: Arc<dyn DialogBackend>) -> Self {
+        self.backend = Some(CustomBackend(backend));
+        self
+    }
+
+    /// Remember where the user left this dialog, under `key`.
+    ///
+    /// The directory, filter and file name the user last picked in a dialog with the same key
+    /// are used for the next one, unless [`FileDialog::set_directory`],
+    /// [`FileDialog::set_current_filter`] or [`FileDialog::set_file_name`] were called. They are kept in `$XDG_STATE_HOME/rfd`
+    /// (`%LOCALAPPDATA%\rfd` on Windows, `~/Library/Application Support/rfd` on Mac).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_persistence_key(mut self, key: impl Into<String>) -> Self {
+        self.persistence_key = Some(key.into());
+        self
+    }
+}
+
+/// Getters for the options set on the builder, meant for [`DialogBackend`] implementations.
+impl FileDialog {
+    /// Filters added with [`FileDialog::add_filter`]
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    /// Name set with [`FileDialog::set_current_filter`]
+    pub fn current_filter(&self) -> Option<&str> {
+        self.current_filter.as_deref()
+    }
+
+    /// Directory set with [`FileDialog::set_directory`]
+    pub fn starting_directory(&self) -> Option<&Path> {
+        self.starting_directory.as_deref()
+    }
+
+    /// File name set with [`FileDialog::set_file_name`]
+    pub fn file_name(&self) -> Option<&OsStr> {
+        self.file_name.as_deref()
+    }
+
+    /// Title set with [`FileDialog::set_title`]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Label set with [`FileDialog::set_accept_label`]
+    pub fn accept_label(&self) -> Option<&str> {
+        self.accept_label.as_deref()
+    }
+
+    /// Value set with [`FileDialog::set_can_create_directories`]
+    pub fn can_create_directories(&self) -> Option<bool> {
+        self.can_create_directories
+    }
+
+    /// Value set with [`FileDialog::set_add_to_recent`]
+    pub fn add_to_recent(&self) -> bool {
+        self.add_to_recent
+    }
+
+    /// Value set with [`FileDialog::set_auto_extension`]
+    pub fn auto_extension(&self) -> bool {
+        self.auto_extension
+    }
+
+    /// Choices added with [`FileDialog::add_choice`] and [`FileDialog::add_checkbox`]
+    pub fn choices(&self) -> &[Choice] {
+        &self.choices
+    }
+
+    /// Value set with [`FileDialog::set_timeout`]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Key set with [`FileDialog::set_persistence_key`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn persistence_key(&self) -> Option<&str> {
+        self.persistence_key.as_deref()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn custom_backend(&self) -> Option<Arc<dyn DialogBackend>> {
+        CustomBackend::resolve(&self.backend)
+    }
+
+    /// Shows the dialog with `show`, then does the [`Bookkeeping`] of the selection.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show<T: Selection>(
+        mut self,
+        save_dialog: bool,
+        show: impl FnOnce(Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let bookkeeping = Bookkeeping::new(&mut self, save_dialog);
+        let res = show(self);
+
+        if let (Some(bookkeeping), Ok(selection)) = (bookkeeping, &res) {
+            bookkeeping.record(selection);
+        }
+        res
+    }
+
+    /// Async variant of [`FileDialog::show`], that can also be closed through a [`DialogHandle`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_async<T: Selection + Default + Send + 'static>(
+        mut self,
+        save_dialog: bool,
+        show: impl FnOnce(Self) -> DialogFutureType<Result<T, Error>>,
+    ) -> DialogFutureType<Result<T, Error>> {
+        let handle = self.handle.clone();
+        let timeout = self.timeout;
+
+        let future = match Bookkeeping::new(&mut self, save_dialog) {
+            Some(bookkeeping) => {
+                let future = show(self);
+                Box::pin(async move {
+                    let res = future.await;
+                    if let Ok(selection) = &res {
+                        bookkeeping.record(selection);
+                    }
+                    res
+                })
+            }
+            None => show(self),
+        };
+
+        DialogHandle::attach(handle, timeout, future, T::default)
+    }
+
+    /// Async variant of [`FileDialog::show`], that can also be closed through a [`DialogHandle`].
+    #[cfg(target_arch = "wasm32")]
+    fn show_async<T: Default + 'static>(
+        self,
+        _save_dialog: bool,
+        show: impl FnOnce(Self) -> DialogFutureType<Result<T, Error>>,
+    ) -> DialogFutureType<Result<T, Error>> {
+        let handle = self.handle.clone();
+        let timeout = self.timeout;
+        DialogHandle::attach(handle, timeout, show(self), T::default)
+    }
+}
+
+/// What rfd does with the paths picked in a dialog, once the user answered it.
+#[cfg(not(target_arch = "wasm32"))]
+struct Bookkeeping {
+    persistence: Option<Persistence>,
+    add_to_recent: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Bookkeeping {
+    /// Prepares the bookkeeping of `dialog`, `None` if there is nothing to do.
+    fn new(dialog: &mut FileDialog, save_dialog: bool) -> Option<Self> {
+        let persistence = Persistence::restore(dialog, save_dialog);
+        let add_to_recent = dialog.add_to_recent;
+        (persistence.is_some() || add_to_recent).then_some(Self {
+            persistence,
+            add_to_recent,
+        })
+    }
+
+    fn record(&self, selection: &impl Selection) {
+        if let Some(persistence) = &self.persistence {
+            persistence.remember(selection);
+        }
+
+        if self.add_to_recent {
+            #[cfg(any(
+                target_os = "linux",
+                target_os = "freebsd",
+                target_os = "dragonfly",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            ))]
+            crate::recent::add(&selection.paths());
+        }
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-use crate::backend::{FilePickerDialogImpl, FileSaveDialogImpl, FolderPickerDialogImpl};
+use crate::backend::{
+    log_error, paths_in_folder, FilePickerDialogImpl, FileSaveDialogImpl, FolderPickerDialogImpl,
+};
 
 #[cfg(not(target_arch = "wasm32"))]
 impl FileDialog {
     /// Pick one file
     pub fn pick_file(self) -> Option<PathBuf> {
-        FilePickerDialogImpl::pick_file(self)
+        log_error("pick_file", self.try_pick_file())
     }
 
     /// Pick multiple files
     pub fn pick_files(self) -> Option<Vec<PathBuf>> {
-        FilePickerDialogImpl::pick_files(self)
+        log_error("pick_files", self.try_pick_files())
     }
 
     /// Pick one folder
     pub fn pick_folder(self) -> Option<PathBuf> {
-        FolderPickerDialogImpl::pick_folder(self)
+        log_error("pick_folder", self.try_pick_folder())
     }
 
     /// Pick multiple folders
+    ///
+    /// kdialog can only select a single folder, so on Linux it is tried after the other backends
+    /// of the fallback policy, see `rfd::linux::FallbackPolicy`, unless it was requested.
     pub fn pick_folders(self) -> Option<Vec<PathBuf>> {
-        FolderPickerDialogImpl::pick_folders(self)
+        log_error("pick_folders", self.try_pick_folders())
     }
 
     /// Opens save file dialog
@@ -141,14 +822,131 @@ impl FileDialog {
     ///     - If user types in filename with extension MacOs will check if it exists in filters list, if not it will display appropriate message
     /// - On GTK
     ///     - It only filters which already existing files get shown to the user
-    ///     - It does not append extensions automatically
+    ///     - It does not append extensions automatically, unless [`FileDialog::set_auto_extension`] is set
     ///     - It does not prevent users from adding any unsupported extension
     /// - On Win:
     ///     - If no extension was provided it will just add currently selected one
     ///     - If selected extension was typed in by the user it will just return
     ///     - If unselected extension was provided it will append selected one at the end, example: `test.png.txt`
     pub fn save_file(self) -> Option<PathBuf> {
-        FileSaveDialogImpl::save_file(self)
+        log_error("save_file", self.try_save_file())
+    }
+
+    /// Pick one file, also telling what was selected besides it, see [`FileResponse`]
+    pub fn pick_file_with_response(self) -> Option<FileResponse<PathBuf>> {
+        log_error("pick_file", self.try_pick_file_with_response())
+    }
+
+    /// Pick multiple files, also telling what was selected besides them, see [`FileResponse`]
+    pub fn pick_files_with_response(self) -> Option<FileResponse<Vec<PathBuf>>> {
+        log_error("pick_files", self.try_pick_files_with_response())
+    }
+
+    /// Opens save file dialog, also telling what was selected besides the file
+    ///
+    /// See [`FileDialog::save_file`] and [`FileResponse`]. The selected filter is reported on
+    /// Linux, zenity excepted.
+    pub fn save_file_with_response(self) -> Option<FileResponse<PathBuf>> {
+        log_error("save_file", self.try_save_file_with_response())
+    }
+
+    /// Asks for a folder to save the files named `names` in, see [`AsyncFileDialog::save_files`]
+    pub fn save_files(self, names: &[impl ToString]) -> Option<Vec<PathBuf>> {
+        log_error("save_files", self.try_save_files(names))
+    }
+}
+
+/// Fallible variants of the dialog functions.
+///
+/// They return `Ok(None)` when the user cancelled the dialog, and an [`Error`] when
+/// no dialog could be shown, eg. because no dialog backend is available.
+#[cfg(not(target_arch = "wasm32"))]
+impl FileDialog {
+    /// Pick one file, see [`FileDialog::pick_file`]
+    pub fn try_pick_file(self) -> Result<Option<PathBuf>, Error> {
+        self.show(false, |dialog| match dialog.custom_backend() {
+            Some(backend) => backend.pick_file(&dialog),
+            None => FilePickerDialogImpl::try_pick_file(dialog),
+        })
+    }
+
+    /// Pick multiple files, see [`FileDialog::pick_files`]
+    pub fn try_pick_files(self) -> Result<Option<Vec<PathBuf>>, Error> {
+        self.show(false, |dialog| match dialog.custom_backend() {
+            Some(backend) => backend.pick_files(&dialog),
+            None => FilePickerDialogImpl::try_pick_files(dialog),
+        })
+    }
+
+    /// Pick one folder, see [`FileDialog::pick_folder`]
+    pub fn try_pick_folder(self) -> Result<Option<PathBuf>, Error> {
+        self.show(false, |dialog| match dialog.custom_backend() {
+            Some(backend) => backend.pick_folder(&dialog),
+            None => FolderPickerDialogImpl::try_pick_folder(dialog),
+        })
+    }
+
+    /// Pick multiple folders, see [`FileDialog::pick_folders`]
+    pub fn try_pick_folders(self) -> Result<Option<Vec<PathBuf>>, Error> {
+        self.show(false, |dialog| match dialog.custom_backend() {
+            Some(backend) => backend.pick_folders(&dialog),
+            None => FolderPickerDialogImpl::try_pick_folders(dialog),
+        })
+    }
+
+    /// Opens save file dialog, see [`FileDialog::save_file`]
+    pub fn try_save_file(self) -> Result<Option<PathBuf>, Error> {
+        self.show(true, |dialog| match dialog.custom_backend() {
+            Some(backend) => backend.save_file(&dialog),
+            None => FileSaveDialogImpl::try_save_file(dialog),
+        })
+    }
+
+    /// Pick one file, see [`FileDialog::pick_file_with_response`]
+    pub fn try_pick_file_with_response(self) -> Result<Option<FileResponse<PathBuf>>, Error> {
+        self.show(false, |dialog| {
+            let choices = dialog.choices.clone();
+            let res = match dialog.custom_backend() {
+                Some(backend) => backend.pick_file(&dialog)?.map(FileResponse::new),
+                None => FilePickerDialogImpl::try_pick_file_with_response(dialog)?,
+            };
+            Ok(res.map(|res| res.with_defaults(&choices)))
+        })
+    }
+
+    /// Pick multiple files, see [`FileDialog::pick_files_with_response`]
+    pub fn try_pick_files_with_response(self) -> Result<Option<FileResponse<Vec<PathBuf>>>, Error> {
+        self.show(false, |dialog| {
+            let choices = dialog.choices.clone();
+            let res = match dialog.custom_backend() {
+                Some(backend) => backend.pick_files(&dialog)?.map(FileResponse::new),
+                None => FilePickerDialogImpl::try_pick_files_with_response(dialog)?,
+            };
+            Ok(res.map(|res| res.with_defaults(&choices)))
+        })
+    }
+
+    /// Opens save file dialog, see [`FileDialog::save_file_with_response`]
+    pub fn try_save_file_with_response(self) -> Result<Option<FileResponse<PathBuf>>, Error> {
+        self.show(true, |dialog| {
+            let choices = dialog.choices.clone();
+            let res = match dialog.custom_backend() {
+                Some(backend) => backend.save_file(&dialog)?.map(FileResponse::new),
+                None => FileSaveDialogImpl::try_save_file_with_response(dialog)?,
+            };
+            Ok(res.map(|res| res.with_defaults(&choices)))
+        })
+    }
+
+    /// Asks for a folder to save files in, see [`FileDialog::save_files`]
+    pub fn try_save_files(self, names: &[impl ToString]) -> Result<Option<Vec<PathBuf>>, Error> {
+        let names: Vec<String> = names.iter().map(ToString::to_string).collect();
+        self.show(false, |dialog| match dialog.custom_backend() {
+            Some(backend) => Ok(backend
+                .pick_folder(&dialog)?
+                .map(|dir| paths_in_folder(&dir, &names))),
+            None => FileSaveDialogImpl::try_save_files(dialog, names),
+        })
     }
 }
 
@@ -182,8 +980,34 @@ impl AsyncFileDialog {
         self
     }
 
+    /// Add MIME type filter, see [`FileDialog::add_mime_filter`].
+    pub fn add_mime_filter(
+        mut self,
+        name: impl Into<String>,
+        mime_types: &[impl ToString],
+    ) -> Self {
+        self.file_dialog = self.file_dialog.add_mime_filter(name, mime_types);
+        self
+    }
+
+    /// Add a filter made of any [`FilterPattern`]s, see [`FileDialog::add_pattern_filter`].
+    pub fn add_pattern_filter(
+        mut self,
+        name: impl Into<String>,
+        patterns: impl IntoIterator<Item = FilterPattern>,
+    ) -> Self {
+        self.file_dialog = self.file_dialog.add_pattern_filter(name, patterns);
+        self
+    }
+
+    /// Select the filter named `name` when the dialog opens, see [`FileDialog::set_current_filter`].
+    pub fn set_current_filter(mut self, name: impl Into<String>) -> Self {
+        self.file_dialog = self.file_dialog.set_current_filter(name);
+        self
+    }
+
     /// Set starting directory of the dialog. Supported platforms:
-    ///   * Linux ([GTK only](https://github.com/PolyMeilex/rfd/issues/42))
+    ///   * Linux
     ///   * Windows
     ///   * Mac
     pub fn set_directory<P: AsRef<Path>>(mut self, path: P) -> Self {
@@ -195,7 +1019,7 @@ impl AsyncFileDialog {
     ///  * Windows
     ///  * Linux
     ///  * Mac
-    pub fn set_file_name(mut self, file_name: impl Into<String>) -> Self {
+    pub fn set_file_name(mut self, file_name: impl Into<OsString>) -> Self {
         self.file_dialog = self.file_dialog.set_file_name(file_name);
         self
     }
@@ -210,11 +1034,17 @@ impl AsyncFileDialog {
         self
     }
 
+    /// Set the label of the button that accepts the dialog, see [`FileDialog::set_accept_label`].
+    pub fn set_accept_label(mut self, label: impl Into<String>) -> Self {
+        self.file_dialog = self.file_dialog.set_accept_label(label);
+        self
+    }
+
     /// Set parent windows explicitly (optional).
     /// Supported platforms:
     ///  * Windows
     ///  * Mac
-    ///  * Linux (XDG only)
+    ///  * Linux (XDG Portal, and zenity on X11)
     pub fn set_parent<W: HasWindowHandle + HasDisplayHandle>(mut self, parent: &W) -> Self {
         self.file_dialog = self.file_dialog.set_parent(parent);
         self
@@ -226,24 +1056,97 @@ impl AsyncFileDialog {
         self.file_dialog = self.file_dialog.set_can_create_directories(can);
         self
     }
+
+    /// Add the picked or saved files to the desktop's recently used files, see [`rfd::recent`](crate::recent).
+    /// Supported platforms:
+    ///  * Linux
+    pub fn set_add_to_recent(mut self, add: bool) -> Self {
+        self.file_dialog = self.file_dialog.set_add_to_recent(add);
+        self
+    }
+
+    /// Append the extension of the selected filter to the name of a saved file,
+    /// see [`FileDialog::set_auto_extension`].
+    pub fn set_auto_extension(mut self, auto: bool) -> Self {
+        self.file_dialog = self.file_dialog.set_auto_extension(auto);
+        self
+    }
+
+    /// Add a combo box of `options` under the files, see [`FileDialog::add_choice`].
+    pub fn add_choice(
+        mut self,
+        id: impl Into<String>,
+        label: impl Into<String>,
+        options: &[(impl ToString, impl ToString)],
+        default: impl Into<String>,
+    ) -> Self {
+        self.file_dialog = self.file_dialog.add_choice(id, label, options, default);
+        self
+    }
+
+    /// Add a checkbox under the files, see [`FileDialog::add_checkbox`].
+    pub fn add_checkbox(
+        mut self,
+        id: impl Into<String>,
+        label: impl Into<String>,
+        default: bool,
+    ) -> Self {
+        self.file_dialog = self.file_dialog.add_checkbox(id, label, default);
+        self
+    }
+
+    /// Dismiss the dialog if the user did not answer it within `timeout`.
+    ///
+    /// The `try_*` functions then resolve to [`Error::TimedOut`], the other ones behave as if
+    /// the dialog was cancelled. Supported platforms:
+    ///  * Linux
+    ///  * Windows and Mac: the future resolves, but the native dialog stays open
+    ///
+    /// WASM32 ignores the timeout, there are no threads to run the timer on.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.file_dialog = self.file_dialog.set_timeout(timeout);
+        self
+    }
+
+    /// Show this dialog with a custom [`DialogBackend`] instead of the native one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_backend(mut self, backend: Arc<dyn DialogBackend>) -> Self {
+        self.file_dialog = self.file_dialog.with_backend(backend);
+        self
+    }
+
+    /// Remember where the user left this dialog, under `key`, see
+    /// [`FileDialog::set_persistence_key`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_persistence_key(mut self, key: impl Into<String>) -> Self {
+        self.file_dialog = self.file_dialog.set_persistence_key(key);
+        self
+    }
+
+    /// Attach this dialog to `handle`, closing the handle dismisses the dialog.
+    pub fn with_handle(mut self, handle: &DialogHandle) -> Self {
+        self.file_dialog.handle = Some(handle.clone());
+        self
+    }
 }
 
-use crate::backend::AsyncFilePickerDialogImpl;
-use crate::backend::AsyncFileSaveDialogImpl;
+use crate::backend::log_error_async;
 #[cfg(not(target_arch = "wasm32"))]
-use crate::backend::AsyncFolderPickerDialogImpl;
+use crate::backend::{response, AsyncFolderPickerDialogImpl};
+use crate::backend::{AsyncFilePickerDialogImpl, AsyncFileSaveDialogImpl};
+use crate::DialogHandle;
 
 use std::future::Future;
 
 impl AsyncFileDialog {
     /// Pick one file
     pub fn pick_file(self) -> impl Future<Output = Option<FileHandle>> {
-        AsyncFilePickerDialogImpl::pick_file_async(self.file_dialog)
+        log_error_async("pick_file", Box::pin(self.try_pick_file()))
     }
 
     /// Pick multiple files
     pub fn pick_files(self) -> impl Future<Output = Option<Vec<FileHandle>>> {
-        AsyncFilePickerDialogImpl::pick_files_async(self.file_dialog)
+        log_error_async("pick_files", Box::pin(self.try_pick_files()))
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -251,15 +1154,18 @@ impl AsyncFileDialog {
     ///
     /// Does not exist in `WASM32`
     pub fn pick_folder(self) -> impl Future<Output = Option<FileHandle>> {
-        AsyncFolderPickerDialogImpl::pick_folder_async(self.file_dialog)
+        log_error_async("pick_folder", Box::pin(self.try_pick_folder()))
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     /// Pick multiple folders
     ///
+    /// kdialog can only select a single folder, so on Linux it is tried after the other backends
+    /// of the fallback policy, see `rfd::linux::FallbackPolicy`, unless it was requested.
+    ///
     /// Does not exist in `WASM32`
     pub fn pick_folders(self) -> impl Future<Output = Option<Vec<FileHandle>>> {
-        AsyncFolderPickerDialogImpl::pick_folders_async(self.file_dialog)
+        log_error_async("pick_folders", Box::pin(self.try_pick_folders()))
     }
 
     /// Opens save file dialog
@@ -271,7 +1177,7 @@ impl AsyncFileDialog {
     ///     - If user types in filename with extension MacOs will check if it exists in filters list, if not it will display appropriate message
     /// - On GTK
     ///     - It only filters which already existing files get shown to the user
-    ///     - It does not append extensions automatically
+    ///     - It does not append extensions automatically, unless [`FileDialog::set_auto_extension`] is set
     ///     - It does not prevent users from adding any unsupported extension
     /// - On Win:
     ///     - If no extension was provided it will just add currently selected one
@@ -280,8 +1186,281 @@ impl AsyncFileDialog {
     /// - On Wasm32:
     ///     - No filtering is applied.
     ///     - `save_file` returns immediately without a dialog prompt.
-    /// Instead the user is prompted by their browser on where to save the file when [`FileHandle::write`] is used.
+    ///       Instead the user is prompted by their browser on where to save the file when [`FileHandle::write`] is used.
     pub fn save_file(self) -> impl Future<Output = Option<FileHandle>> {
-        AsyncFileSaveDialogImpl::save_file_async(self.file_dialog)
+        log_error_async("save_file", Box::pin(self.try_save_file()))
+    }
+
+    /// Pick one file, also telling what was selected besides it,
+    /// see [`FileDialog::pick_file_with_response`]
+    pub fn pick_file_with_response(self) -> impl Future<Output = Option<FileResponse<FileHandle>>> {
+        log_error_async("pick_file", Box::pin(self.try_pick_file_with_response()))
+    }
+
+    /// Pick multiple files, also telling what was selected besides them,
+    /// see [`FileDialog::pick_files_with_response`]
+    pub fn pick_files_with_response(
+        self,
+    ) -> impl Future<Output = Option<FileResponse<Vec<FileHandle>>>> {
+        log_error_async("pick_files", Box::pin(self.try_pick_files_with_response()))
+    }
+
+    /// Opens save file dialog, also telling what was selected besides the file,
+    /// see [`FileDialog::save_file_with_response`]
+    pub fn save_file_with_response(self) -> impl Future<Output = Option<FileResponse<FileHandle>>> {
+        log_error_async("save_file", Box::pin(self.try_save_file_with_response()))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Asks for a folder to save the files named `names` in, and returns a handle to write
+    /// each of them to, in the same order.
+    ///
+    /// ```no_run
+    /// # async fn export() {
+    /// let files = rfd::AsyncFileDialog::new()
+    ///     .save_files(&["a.png", "b.png"])
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    /// On Linux this uses the `SaveFiles` method of the XDG Desktop Portal, which also grants
+    /// sandboxed applications access to the files. Elsewhere, and when the portal is not
+    /// available, a folder is picked instead. The files are then not overwritten: names that
+    /// are taken are numbered like `a (2).png`.
+    ///
+    /// Does not exist in `WASM32`
+    pub fn save_files(
+        self,
+        names: &[impl ToString],
+    ) -> impl Future<Output = Option<Vec<FileHandle>>> {
+        let names = names.iter().map(ToString::to_string).collect();
+        log_error_async("save_files", self.save_files_in_folder(names))
+    }
+}
+
+/// Fallible variants of the dialog functions.
+///
+/// They resolve to `Ok(None)` when the user cancelled the dialog, and to an [`Error`] when
+/// no dialog could be shown, eg. because no dialog backend is available.
+impl AsyncFileDialog {
+    /// Pick one file, see [`AsyncFileDialog::pick_file`]
+    pub fn try_pick_file(self) -> impl Future<Output = Result<Option<FileHandle>, Error>> {
+        self.file_dialog.show_async(false, |dialog| {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(backend) = dialog.custom_backend() {
+                return backend.pick_file_async(dialog);
+            }
+            AsyncFilePickerDialogImpl::try_pick_file_async(dialog)
+        })
+    }
+
+    /// Pick multiple files, see [`AsyncFileDialog::pick_files`]
+    pub fn try_pick_files(self) -> impl Future<Output = Result<Option<Vec<FileHandle>>, Error>> {
+        self.file_dialog.show_async(false, |dialog| {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(backend) = dialog.custom_backend() {
+                return backend.pick_files_async(dialog);
+            }
+            AsyncFilePickerDialogImpl::try_pick_files_async(dialog)
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Pick one folder, see [`AsyncFileDialog::pick_folder`]
+    ///
+    /// Does not exist in `WASM32`
+    pub fn try_pick_folder(self) -> impl Future<Output = Result<Option<FileHandle>, Error>> {
+        self.file_dialog
+            .show_async(false, |dialog| match dialog.custom_backend() {
+                Some(backend) => backend.pick_folder_async(dialog),
+                None => AsyncFolderPickerDialogImpl::try_pick_folder_async(dialog),
+            })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Pick multiple folders, see [`AsyncFileDialog::pick_folders`]
+    ///
+    /// Does not exist in `WASM32`
+    pub fn try_pick_folders(self) -> impl Future<Output = Result<Option<Vec<FileHandle>>, Error>> {
+        self.file_dialog
+            .show_async(false, |dialog| match dialog.custom_backend() {
+                Some(backend) => backend.pick_folders_async(dialog),
+                None => AsyncFolderPickerDialogImpl::try_pick_folders_async(dialog),
+            })
+    }
+
+    /// Opens save file dialog, see [`AsyncFileDialog::save_file`]
+    pub fn try_save_file(self) -> impl Future<Output = Result<Option<FileHandle>, Error>> {
+        self.file_dialog.show_async(true, |dialog| {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(backend) = dialog.custom_backend() {
+                return backend.save_file_async(dialog);
+            }
+            AsyncFileSaveDialogImpl::try_save_file_async(dialog)
+        })
+    }
+
+    /// Pick one file, see [`AsyncFileDialog::pick_file_with_response`]
+    pub fn try_pick_file_with_response(
+        self,
+    ) -> impl Future<Output = Result<Option<FileResponse<FileHandle>>, Error>> {
+        self.file_dialog.show_async(false, |dialog| {
+            let choices = dialog.choices.clone();
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(backend) = dialog.custom_backend() {
+                let future = response(backend.pick_file_async(dialog));
+                return Box::pin(answer_choices(choices, future));
+            }
+            let future = AsyncFilePickerDialogImpl::try_pick_file_with_response_async(dialog);
+            Box::pin(answer_choices(choices, future))
+        })
+    }
+
+    /// Pick multiple files, see [`AsyncFileDialog::pick_files_with_response`]
+    pub fn try_pick_files_with_response(
+        self,
+    ) -> impl Future<Output = Result<Option<FileResponse<Vec<FileHandle>>>, Error>> {
+        self.file_dialog.show_async(false, |dialog| {
+            let choices = dialog.choices.clone();
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(backend) = dialog.custom_backend() {
+                let future = response(backend.pick_files_async(dialog));
+                return Box::pin(answer_choices(choices, future));
+            }
+            let future = AsyncFilePickerDialogImpl::try_pick_files_with_response_async(dialog);
+            Box::pin(answer_choices(choices, future))
+        })
+    }
+
+    /// Opens save file dialog, see [`AsyncFileDialog::save_file_with_response`]
+    pub fn try_save_file_with_response(
+        self,
+    ) -> impl Future<Output = Result<Option<FileResponse<FileHandle>>, Error>> {
+        self.file_dialog.show_async(true, |dialog| {
+            let choices = dialog.choices.clone();
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(backend) = dialog.custom_backend() {
+                let future = response(backend.save_file_async(dialog));
+                return Box::pin(answer_choices(choices, future));
+            }
+            let future = AsyncFileSaveDialogImpl::try_save_file_with_response_async(dialog);
+            Box::pin(answer_choices(choices, future))
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AsyncFileDialog {
+    /// Asks for a folder to save files in, see [`AsyncFileDialog::save_files`]
+    ///
+    /// Does not exist in `WASM32`
+    pub fn try_save_files(
+        self,
+        names: &[impl ToString],
+    ) -> impl Future<Output = Result<Option<Vec<FileHandle>>, Error>> {
+        let names = names.iter().map(ToString::to_string).collect();
+        self.save_files_in_folder(names)
+    }
+
+    fn save_files_in_folder(
+        self,
+        names: Vec<String>,
+    ) -> DialogFutureType<Result<Option<Vec<FileHandle>>, Error>> {
+        self.file_dialog
+            .show_async(false, |dialog| match dialog.custom_backend() {
+                Some(backend) => {
+                    let future = backend.pick_folder_async(dialog);
+                    Box::pin(async move {
+                        let paths = future.await?.map(|dir| paths_in_folder(dir.path(), &names));
+                        Ok(paths.map(|paths| paths.into_iter().map(FileHandle::wrap).collect()))
+                    })
+                }
+                None => AsyncFileSaveDialogImpl::try_save_files_async(dialog, names),
+            })
+    }
+}
+
+/// Answers the choices the backend did not with their defaults.
+async fn answer_choices<T>(
+    choices: Vec<Choice>,
+    future: impl Future<Output = Result<Option<FileResponse<T>>, Error>>,
+) -> Result<Option<FileResponse<T>>, Error> {
+    Ok(future.await?.map(|res| res.with_defaults(&choices)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_patterns() {
+        let dialog = FileDialog::default()
+            .add_filter("Images", &["png", ".JPG", "*.tar.gz", "*", "tar.*"])
+            .add_pattern_filter("Make", [FilterPattern::glob("Makefile")]);
+
+        assert_eq!(
+            dialog.filters[0].patterns(),
+            [
+                FilterPattern::Extension("png".into()),
+                FilterPattern::Extension("JPG".into()),
+                FilterPattern::Extension("tar.gz".into()),
+                FilterPattern::Glob("*".into()),
+                FilterPattern::Glob("*.tar.*".into()),
+            ]
+        );
+        assert_eq!(
+            dialog.filters[0].to_globs(true)[..3],
+            [
+                "*.[pP][nN][gG]",
+                "*.[jJ][pP][gG]",
+                "*.[tT][aA][rR].[gG][zZ]"
+            ]
+        );
+        assert_eq!(dialog.filters[1].to_globs(true), ["Makefile"]);
+    }
+
+    #[test]
+    fn filter_matches() {
+        let dialog = FileDialog::default()
+            .add_filter("Images", &["png", "tar.*"])
+            .add_filter("All", &["*"])
+            .add_pattern_filter("Make", [FilterPattern::glob("[Mm]akefile*")])
+            .add_pattern_filter("Logs", [FilterPattern::glob("log-[!a-z]?.txt")]);
+        let matches = |filter: usize, name: &str| dialog.filters[filter].matches(Path::new(name));
+
+        assert!(matches(0, "/a/photo.PNG"));
+        assert!(matches(0, "b.tar.gz"));
+        assert!(!matches(0, "png"));
+        assert!(matches(1, "anything"));
+        assert!(matches(2, "Makefile.am"));
+        assert!(matches(2, "makefile"));
+        assert!(!matches(2, "GNUmakefile"));
+        assert!(matches(3, "log-1a.txt"));
+        assert!(!matches(3, "log-ab.txt"));
+        assert!(!matches(3, "log-1.txt"));
+    }
+
+    #[test]
+    fn choice_defaults() {
+        let dialog = FileDialog::default()
+            .add_choice(
+                "encoding",
+                "Encoding",
+                &[("utf8", "UTF-8"), ("latin1", "Latin-1")],
+                "utf8",
+            )
+            .add_checkbox("hidden", "Include hidden layers", false);
+
+        let mut response = FileResponse::new(PathBuf::from("/a"));
+        response.choices.insert("encoding".into(), "latin1".into());
+        let response = response.with_defaults(dialog.choices());
+
+        assert_eq!(response.choice("encoding"), Some("latin1"));
+        assert_eq!(response.choice("hidden"), Some("false"));
+        assert!(!response.checkbox("hidden"));
+        assert!(dialog.choices()[1].is_checkbox());
     }
 }
@@ -8,14 +8,18 @@
 #[cfg(not(target_arch = "wasm32"))]
 mod native;
 #[cfg(not(target_arch = "wasm32"))]
-pub use native::FileHandle;
+pub use native::{FileHandle, FileReader};
+#[cfg(not(target_arch = "wasm32"))]
+mod writer;
+#[cfg(not(target_arch = "wasm32"))]
+pub use writer::FileWriter;
 
 #[cfg(target_arch = "wasm32")]
 mod web;
 #[cfg(target_arch = "wasm32")]
-pub use web::FileHandle;
-#[cfg(target_arch = "wasm32")]
 pub(crate) use web::WasmFileHandleKind;
+#[cfg(target_arch = "wasm32")]
+pub use web::{FileHandle, FileReader};
 
 #[cfg(test)]
 mod tests {
@@ -25,9 +29,145 @@ mod tests {
     fn fn_def_check() {
         let _ = FileHandle::wrap;
         let _ = FileHandle::read;
+        let _ = FileHandle::try_read;
+        let _ = FileHandle::reader;
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = FileHandle::write_atomic;
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = FileHandle::create;
+        let _ = FileHandle::read_range;
+        let _ = FileHandle::read_to_string;
+        let _ = FileHandle::size;
+        let _ = FileHandle::modified;
+        let _ = FileHandle::mime_type;
         #[cfg(feature = "file-handle-inner")]
         let _ = FileHandle::inner;
         #[cfg(not(target_arch = "wasm32"))]
         let _ = FileHandle::path;
     }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn metadata_and_ranges() {
+        let path = std::env::temp_dir().join(format!("rfd-file-handle-{}", std::process::id()));
+        std::fs::write(&path, "hello world\n").unwrap();
+        let file = FileHandle::from(path.clone());
+
+        assert_eq!(file.size().unwrap(), 12);
+        assert!(file.modified().is_ok());
+        let range = futures::executor::block_on(file.read_range(6, 100)).unwrap();
+        assert_eq!(range, b"world\n");
+        let text = futures::executor::block_on(file.read_to_string()).unwrap();
+        assert_eq!(text, "hello world\n");
+        assert_eq!(
+            futures::executor::block_on(file.mime_type()).unwrap(),
+            "text/plain"
+        );
+
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR").unwrap();
+        assert_eq!(
+            futures::executor::block_on(file.mime_type()).unwrap(),
+            "image/png"
+        );
+        assert!(futures::executor::block_on(file.read_to_string()).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn streaming_reads() {
+        use futures::{AsyncReadExt, StreamExt};
+        use std::sync::{Arc, Mutex};
+
+        let path = std::env::temp_dir().join(format!("rfd-file-reader-{}", std::process::id()));
+        std::fs::write(&path, "0123456789").unwrap();
+        let file = FileHandle::from(path.clone());
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let reported = progress.clone();
+        let reader = file
+            .reader()
+            .chunk_size(4)
+            .on_progress(move |read, total| reported.lock().unwrap().push((read, total)));
+        let chunks: Vec<_> = futures::executor::block_on(reader.collect::<Vec<_>>())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(chunks, [&b"0123"[..], b"4567", b"89"]);
+        assert_eq!(
+            *progress.lock().unwrap(),
+            [(4, Some(10)), (8, Some(10)), (10, Some(10))]
+        );
+
+        let mut bytes = Vec::new();
+        let mut reader = file.reader().chunk_size(3);
+        futures::executor::block_on(reader.read_to_end(&mut bytes)).unwrap();
+        assert_eq!(bytes, b"0123456789");
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(futures::executor::block_on(file.try_read()).is_err());
+        let mut reader = file.reader();
+        let first = futures::executor::block_on(reader.next());
+        assert!(matches!(first, Some(Err(_))));
+        assert!(futures::executor::block_on(reader.next()).is_none());
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn atomic_writes() {
+        use futures::AsyncWriteExt;
+        #[cfg(unix)]
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("rfd-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("doc.txt");
+        std::fs::write(&path, "old").unwrap();
+        let file = FileHandle::from(path.clone());
+
+        #[cfg(unix)]
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        futures::executor::block_on(file.write_atomic_with_backup(b"new")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        #[cfg(unix)]
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("doc.txt~")).unwrap(),
+            "old"
+        );
+
+        // Nothing changes until the writer is closed, and dropping it leaves no trace.
+        let mut writer = file.create();
+        futures::executor::block_on(writer.write_all(b"dropped")).unwrap();
+        futures::executor::block_on(writer.flush()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        drop(writer);
+
+        // Temporary names don't grow with the name of the destination.
+        let long = FileHandle::from(dir.join("n".repeat(255)));
+        futures::executor::block_on(long.write_atomic(b"long")).unwrap();
+        std::fs::remove_file(long.path()).unwrap();
+
+        let mut writer = file.create();
+        futures::executor::block_on(async {
+            writer.write_all(b"streamed ").await.unwrap();
+            writer.write_all(b"content").await.unwrap();
+            writer.close().await.unwrap();
+        });
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "streamed content");
+
+        let mut names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["doc.txt", "doc.txt~"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1,32 +1,38 @@
+use super::writer::{AtomicFile, FileWriter};
+
 use std::{
     future::Future,
+    io::{self, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     pin::Pin,
-    sync::{Arc, Mutex},
+    sync::{mpsc, Arc, Mutex},
     task::{Context, Poll, Waker},
+    time::SystemTime,
 };
 
-#[derive(Default)]
-struct ReaderState {
-    res: Option<std::io::Result<Vec<u8>>>,
+struct BackgroundState<T> {
+    res: Option<T>,
     waker: Option<Waker>,
 }
 
-struct Reader {
-    state: Arc<Mutex<ReaderState>>,
+/// Runs blocking file I/O on a `std::thread`, and resolves to its result.
+struct Background<T> {
+    state: Arc<Mutex<BackgroundState<T>>>,
 }
 
-impl Reader {
-    fn new(path: &Path) -> Self {
-        let state: Arc<Mutex<ReaderState>> = Arc::new(Mutex::new(Default::default()));
+impl<T: Send + 'static> Background<T> {
+    fn spawn(name: &str, f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let state = Arc::new(Mutex::new(BackgroundState {
+            res: None,
+            waker: None,
+        }));
 
         {
-            let path = path.to_owned();
             let state = state.clone();
             std::thread::Builder::new()
-                .name("rfd_file_read".into())
+                .name(name.into())
                 .spawn(move || {
-                    let res = std::fs::read(path);
+                    let res = f();
 
                     let mut state = state.lock().unwrap();
                     state.res.replace(res);
@@ -42,13 +48,13 @@ impl Reader {
     }
 }
 
-impl Future for Reader {
-    type Output = Vec<u8>;
+impl<T> Future for Background<T> {
+    type Output = T;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut state = self.state.lock().unwrap();
         if let Some(res) = state.res.take() {
-            Poll::Ready(res.unwrap())
+            Poll::Ready(res)
         } else {
             state.waker.replace(ctx.waker().clone());
             Poll::Pending
@@ -56,56 +62,258 @@ impl Future for Reader {
     }
 }
 
-struct WriterState {
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How many chunks are read ahead of the consumer of a [`FileReader`].
+const READ_AHEAD: usize = 2;
+
+struct ReaderShared {
     waker: Option<Waker>,
-    res: Option<std::io::Result<()>>,
+    total: Option<u64>,
 }
 
-struct Writer {
-    state: Arc<Mutex<WriterState>>,
+/// The thread of a started [`FileReader`].
+struct Running {
+    chunks: mpsc::Receiver<io::Result<Vec<u8>>>,
+    shared: Arc<Mutex<ReaderShared>>,
 }
 
-impl Writer {
-    fn new(path: &Path, bytes: &[u8]) -> Self {
-        let state = Arc::new(Mutex::new(WriterState {
+impl Running {
+    fn spawn(path: PathBuf, chunk_size: usize) -> Self {
+        // A bounded channel, so the thread waits for the chunks to be consumed.
+        let (sender, chunks) = mpsc::sync_channel(READ_AHEAD);
+        let shared = Arc::new(Mutex::new(ReaderShared {
             waker: None,
-            res: None,
+            total: None,
         }));
 
-        {
-            let path = path.to_owned();
-            let bytes = bytes.to_owned();
-            let state = state.clone();
-            std::thread::Builder::new()
-                .name("rfd_file_write".into())
-                .spawn(move || {
-                    let res = std::fs::write(path, bytes);
+        let state = shared.clone();
+        let wake = move || {
+            if let Some(waker) = state.lock().unwrap().waker.take() {
+                waker.wake();
+            }
+        };
+        let total = shared.clone();
+        std::thread::Builder::new()
+            .name("rfd_file_read".into())
+            .spawn(move || {
+                let send = |chunk| {
+                    let sent = sender.send(chunk).is_ok();
+                    wake();
+                    sent
+                };
 
-                    let mut state = state.lock().unwrap();
-                    state.res.replace(res);
+                match std::fs::File::open(path) {
+                    Ok(mut file) => {
+                        total.lock().unwrap().total = file.metadata().ok().map(|meta| meta.len());
 
-                    if let Some(waker) = state.waker.take() {
-                        waker.wake();
+                        loop {
+                            let mut chunk = Vec::with_capacity(chunk_size);
+                            match (&mut file).take(chunk_size as u64).read_to_end(&mut chunk) {
+                                Ok(0) => break,
+                                Ok(_) if send(Ok(chunk)) => {}
+                                Ok(_) => break,
+                                Err(err) => {
+                                    send(Err(err));
+                                    break;
+                                }
+                            }
+                        }
                     }
-                })
-                .unwrap();
+                    Err(err) => {
+                        send(Err(err));
+                    }
+                }
+
+                // Dropping the sender ends the stream, which the consumer may be waiting for.
+                drop(sender);
+                wake();
+            })
+            .unwrap();
+
+        Self { chunks, shared }
+    }
+}
+
+/// Called with the number of bytes read so far and the size of the file.
+type Progress = Box<dyn FnMut(u64, Option<u64>) + Send>;
+
+/// Reads a file in chunks, see [`FileHandle::reader`].
+///
+/// Chunks are read on a `std::thread`, a couple of them ahead of the consumer at most.
+pub struct FileReader {
+    path: PathBuf,
+    chunk_size: usize,
+    progress: Option<Progress>,
+    running: Option<Running>,
+    bytes_read: u64,
+    leftover: Vec<u8>,
+    leftover_pos: usize,
+}
+
+impl FileReader {
+    /// Reads chunks of `chunk_size` bytes, 64 KiB by default. The last one can be shorter.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Calls `progress` with the number of bytes read so far and the size of the file, if known,
+    /// after every chunk.
+    pub fn on_progress(mut self, progress: impl FnMut(u64, Option<u64>) + Send + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// The number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    fn poll_chunk(&mut self, ctx: &mut Context<'_>) -> Poll<Option<io::Result<Vec<u8>>>> {
+        let running = self
+            .running
+            .get_or_insert_with(|| Running::spawn(self.path.clone(), self.chunk_size));
+
+        // The waker goes first, so a chunk sent in between is not missed.
+        running.shared.lock().unwrap().waker = Some(ctx.waker().clone());
+        let chunk = match running.chunks.try_recv() {
+            Ok(chunk) => chunk,
+            Err(mpsc::TryRecvError::Empty) => return Poll::Pending,
+            Err(mpsc::TryRecvError::Disconnected) => return Poll::Ready(None),
+        };
+
+        if let Ok(chunk) = &chunk {
+            self.bytes_read += chunk.len() as u64;
+            let total = running.shared.lock().unwrap().total;
+            if let Some(progress) = &mut self.progress {
+                progress(self.bytes_read, total);
+            }
         }
+        Poll::Ready(Some(chunk))
+    }
+}
 
-        Self { state }
+impl futures_core::Stream for FileReader {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // Bytes left over by `AsyncRead` come first.
+        if this.leftover_pos < this.leftover.len() {
+            let leftover = this.leftover.split_off(this.leftover_pos);
+            this.leftover.clear();
+            this.leftover_pos = 0;
+            return Poll::Ready(Some(Ok(leftover)));
+        }
+
+        this.poll_chunk(ctx)
     }
 }
 
-impl Future for Writer {
-    type Output = std::io::Result<()>;
+impl futures_io::AsyncRead for FileReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        ctx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
 
-    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
-        let mut state = self.state.lock().unwrap();
-        if let Some(res) = state.res.take() {
-            Poll::Ready(res)
-        } else {
-            state.waker.replace(ctx.waker().clone());
-            Poll::Pending
+        while this.leftover_pos == this.leftover.len() {
+            match this.poll_chunk(ctx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.leftover = chunk;
+                    this.leftover_pos = 0;
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err)),
+                Poll::Ready(None) => return Poll::Ready(Ok(0)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let leftover = &this.leftover[this.leftover_pos..];
+        let len = leftover.len().min(buf.len());
+        buf[..len].copy_from_slice(&leftover[..len]);
+        this.leftover_pos += len;
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl std::fmt::Debug for FileReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileReader")
+            .field("path", &self.path)
+            .field("chunk_size", &self.chunk_size)
+            .field("bytes_read", &self.bytes_read)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads at most `len` bytes of the file at `path`, from `offset`.
+fn read_range(path: &Path, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut bytes = Vec::new();
+    file.take(len as u64).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The MIME type of the file at `path`.
+fn mime_type(path: &Path) -> io::Result<String> {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    if let Some(mime_type) = crate::backend::linux::mime::sniff(path)? {
+        return Ok(mime_type);
+    }
+
+    if path.is_dir() {
+        return Ok("inode/directory".to_owned());
+    }
+
+    let head = read_range(path, 0, 512)?;
+    Ok(sniff(&head).to_owned())
+}
+
+/// The MIME type of a file starting with `head`, for a few common formats, otherwise only
+/// telling text from binary files.
+fn sniff(head: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+    ];
+
+    if let Some((_, mime_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        return mime_type;
+    }
+
+    // The last character may have been cut in the middle.
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&head[..err.valid_up_to()]).unwrap()
         }
+        Err(_) => return "application/octet-stream",
+    };
+    if text
+        .chars()
+        .any(|c| c.is_control() && !c.is_ascii_whitespace())
+    {
+        "application/octet-stream"
+    } else {
+        "text/plain"
     }
 }
 
@@ -122,11 +330,13 @@ impl FileHandle {
     }
 
     /// Get name of a file
+    ///
+    /// Names that are not valid UTF-8 are converted lossily, [`FileHandle::path`] has the exact
+    /// name.
     pub fn file_name(&self) -> String {
         self.0
             .file_name()
-            .and_then(|f| f.to_str())
-            .map(|f| f.to_string())
+            .map(|f| f.to_string_lossy().into_owned())
             .unwrap_or_default()
     }
 
@@ -142,17 +352,208 @@ impl FileHandle {
     /// On native platforms it spawns a `std::thread` in the background.
     ///
     /// `This fn exists solely to keep native api in pair with async only web api.`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't be read, see [`FileHandle::try_read`].
     pub async fn read(&self) -> Vec<u8> {
-        Reader::new(&self.0).await
+        self.try_read().await.unwrap()
+    }
+
+    /// Reads a file asynchronously, returning an error if it can't be read.
+    ///
+    /// On native platforms it spawns a `std::thread` in the background.
+    pub async fn try_read(&self) -> io::Result<Vec<u8>> {
+        let path = self.0.clone();
+        Background::spawn("rfd_file_read", move || std::fs::read(path)).await
+    }
+
+    /// Reads the file in chunks, without loading it whole in memory.
+    ///
+    /// The returned [`FileReader`] is both a [`Stream`](futures_core::Stream) of chunks and an
+    /// [`AsyncRead`](futures_io::AsyncRead).
+    pub fn reader(&self) -> FileReader {
+        FileReader {
+            path: self.0.clone(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            progress: None,
+            running: None,
+            bytes_read: 0,
+            leftover: Vec::new(),
+            leftover_pos: 0,
+        }
+    }
+
+    /// Reads at most `len` bytes of the file, starting at `offset`, asynchronously.
+    ///
+    /// Fewer bytes are returned when the file ends before.
+    ///
+    /// On native platforms it spawns a `std::thread` in the background.
+    pub async fn read_range(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let path = self.0.clone();
+        Background::spawn("rfd_file_read", move || read_range(&path, offset, len)).await
+    }
+
+    /// Reads a UTF-8 text file asynchronously.
+    ///
+    /// On native platforms it spawns a `std::thread` in the background.
+    pub async fn read_to_string(&self) -> io::Result<String> {
+        let path = self.0.clone();
+        Background::spawn("rfd_file_read", move || std::fs::read_to_string(path)).await
+    }
+
+    /// Size of the file in bytes.
+    pub fn size(&self) -> io::Result<u64> {
+        Ok(self.0.metadata()?.len())
+    }
+
+    /// Time the file was last modified.
+    pub fn modified(&self) -> io::Result<SystemTime> {
+        self.0.metadata()?.modified()
+    }
+
+    /// MIME type of the file, like `image/png`, asynchronously.
+    ///
+    /// On Linux & BSDs it is found from the name and the content of the file with the shared
+    /// MIME-info database. Elsewhere a few common formats are recognized from the content, and
+    /// other files are `text/plain` or `application/octet-stream`.
+    pub async fn mime_type(&self) -> io::Result<String> {
+        let path = self.0.clone();
+        Background::spawn("rfd_file_read", move || mime_type(&path)).await
     }
 
     /// Writes a file asynchronously.
     ///
+    /// A crash in the middle leaves the file half written, [`FileHandle::write_atomic`] does not.
+    ///
     /// On native platforms it spawns a `std::thread` in the background.
     ///
     /// `This fn exists solely to keep native api in pair with async only web api.`
     pub async fn write(&self, data: &[u8]) -> std::io::Result<()> {
-        Writer::new(&self.0, data).await
+        let path = self.0.clone();
+        let data = data.to_owned();
+        Background::spawn("rfd_file_write", move || std::fs::write(path, data)).await
+    }
+
+    /// Writes a file asynchronously, without ever leaving it half written.
+    ///
+    /// The data is written to a temporary file next to it, synced to disk, and renamed over the
+    /// file, which keeps its permissions. A crash leaves either the previous or the new content.
+    ///
+    /// On native platforms it spawns a `std::thread` in the background.
+    pub async fn write_atomic(&self, data: &[u8]) -> io::Result<()> {
+        self.write_atomic_inner(data, false).await
+    }
+
+    /// Like [`FileHandle::write_atomic`], also keeping the previous content of the file next to
+    /// it, with a `~` appended to its name.
+    pub async fn write_atomic_with_backup(&self, data: &[u8]) -> io::Result<()> {
+        self.write_atomic_inner(data, true).await
+    }
+
+    async fn write_atomic_inner(&self, data: &[u8], keep_backup: bool) -> io::Result<()> {
+        let path = self.0.clone();
+        let data = data.to_owned();
+        Background::spawn("rfd_file_write", move || {
+            let mut file = AtomicFile::create(&path)?;
+            file.write_all(&data)?;
+            file.commit(keep_backup)
+        })
+        .await
+    }
+
+    /// Writes the file in chunks, as an [`AsyncWrite`](futures_io::AsyncWrite).
+    ///
+    /// Like [`FileHandle::write_atomic`], the file is only replaced once the [`FileWriter`] is
+    /// closed, dropping it before leaves the file untouched.
+    ///
+    /// Does not exist in `WASM32`
+    pub fn create(&self) -> FileWriter {
+        FileWriter::create(self.0.clone())
+    }
+
+    /// The ID of the document, when the file was given to a sandboxed (eg. Flatpak) application
+    /// through the document portal, ie. its path is in `/run/user/<uid>/doc/<ID>/`.
+    ///
+    /// `None` outside of the document portal, and on platforms other than Linux & BSDs.
+    pub fn document_id(&self) -> Option<String> {
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        return crate::backend::linux::documents::document_id(&self.0);
+
+        #[allow(unreachable_code)]
+        None
+    }
+
+    /// Whether the file was given to a sandboxed application through the document portal, see
+    /// [`FileHandle::document_id`].
+    pub fn is_document(&self) -> bool {
+        self.document_id().is_some()
+    }
+
+    /// The path of the file outside of the sandbox, for a file of the document portal.
+    ///
+    /// Files that are not in the document portal are returned as is. `None` when the portal
+    /// does not tell, which it does from version 5 of its `Documents` interface, and only to
+    /// applications that are allowed to know.
+    ///
+    /// #### Linux & BSDs only, behind the `xdg-portal` feature flag
+    #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ),
+        feature = "xdg-portal"
+    ))]
+    pub async fn host_path(&self) -> Result<Option<PathBuf>, crate::Error> {
+        use crate::backend::linux::documents;
+
+        let Some((id, rest)) = documents::split(&self.0) else {
+            return Ok(Some(self.0.clone()));
+        };
+
+        let host_path = crate::backend::xdg_desktop_portal::host_path(&id).await?;
+        Ok(host_path.map(|document| documents::host_path(document, &rest)))
+    }
+
+    /// Keeps access to a file of the document portal after the application restarts.
+    ///
+    /// Files picked in a dialog are only shared with a sandboxed application until it exits.
+    /// This makes the file a persistent document, and returns a handle to its path in the
+    /// portal, which can be stored and opened again later with `FileHandle::from`.
+    ///
+    /// Files that are not in the document portal are returned as is, they need no permission.
+    ///
+    /// #### Linux & BSDs only, behind the `xdg-portal` feature flag
+    #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ),
+        feature = "xdg-portal"
+    ))]
+    pub async fn persist(&self) -> Result<FileHandle, crate::Error> {
+        if !self.is_document() {
+            return Ok(self.clone());
+        }
+
+        let id = crate::backend::xdg_desktop_portal::add_persistent(&self.0).await?;
+        let mut path = crate::backend::linux::documents::mount_point().join(id);
+        if let Some(name) = self.0.file_name() {
+            path.push(name);
+        }
+        Ok(Self(path))
     }
 
     /// Unwraps a `FileHandle` and returns inner type.
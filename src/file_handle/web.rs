@@ -1,6 +1,21 @@
 use crate::file_dialog::FileDialog;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
+
+/// Reads the whole content of `blob`, through `Blob.arrayBuffer()` which needs no callback to
+/// be kept alive.
+async fn read_blob(blob: &web_sys::Blob) -> Result<Vec<u8>, JsValue> {
+    let buffer = wasm_bindgen_futures::JsFuture::from(blob.array_buffer()).await?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+fn js_error(err: JsValue) -> io::Error {
+    io::Error::other(format!("{err:?}"))
+}
 
 #[derive(Clone, Debug)]
 pub(crate) enum WasmFileHandleKind {
@@ -25,7 +40,11 @@ impl FileHandle {
     pub fn file_name(&self) -> String {
         match &self.0 {
             WasmFileHandleKind::Readable(x) => x.name(),
-            WasmFileHandleKind::Writable(x) => x.file_name.clone().unwrap_or_default(),
+            WasmFileHandleKind::Writable(x) => x
+                .file_name
+                .as_ref()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
         }
     }
 
@@ -37,35 +56,86 @@ impl FileHandle {
     // }
 
     pub async fn read(&self) -> Vec<u8> {
-        let promise = js_sys::Promise::new(&mut move |res, _rej| {
-            let file_reader = web_sys::FileReader::new().unwrap();
+        if let WasmFileHandleKind::Readable(reader) = &self.0 {
+            read_blob(reader).await.unwrap()
+        } else {
+            panic!("This File Handle doesn't support reading. Use `pick_file` to get a readable FileHandle");
+        }
+    }
 
-            let fr = file_reader.clone();
-            let closure = Closure::wrap(Box::new(move || {
-                res.call1(&JsValue::undefined(), &fr.result().unwrap())
-                    .unwrap();
-            }) as Box<dyn FnMut()>);
+    /// Reads a file, returning an error if it can't be read.
+    pub async fn try_read(&self) -> io::Result<Vec<u8>> {
+        read_blob(self.readable()?).await.map_err(js_error)
+    }
 
-            file_reader.set_onload(Some(closure.as_ref().unchecked_ref()));
+    /// Reads the file in chunks, without loading it whole in memory.
+    ///
+    /// The returned [`FileReader`] is both a [`Stream`](futures_core::Stream) of chunks and an
+    /// [`AsyncRead`](futures_io::AsyncRead).
+    pub fn reader(&self) -> FileReader {
+        let (file, error) = match self.readable() {
+            Ok(file) => (Some(file.clone()), None),
+            Err(err) => (None, Some(err)),
+        };
+        FileReader {
+            file,
+            error,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            progress: None,
+            pending: None,
+            bytes_read: 0,
+            leftover: Vec::new(),
+            leftover_pos: 0,
+        }
+    }
 
-            closure.forget();
+    /// Reads at most `len` bytes of the file, starting at `offset`.
+    ///
+    /// Fewer bytes are returned when the file ends before.
+    pub async fn read_range(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let file = self.readable()?;
+        let end = offset.saturating_add(len as u64);
+        let blob = file
+            .slice_with_f64_and_f64(offset as f64, end as f64)
+            .map_err(js_error)?;
+        read_blob(&blob).await.map_err(js_error)
+    }
 
-            if let WasmFileHandleKind::Readable(reader) = &self.0 {
-                file_reader.read_as_array_buffer(reader).unwrap();
-            } else {
-                panic!("This File Handle doesn't support reading. Use `pick_file` to get a readable FileHandle");
-            }
-        });
+    /// Reads a UTF-8 text file.
+    pub async fn read_to_string(&self) -> io::Result<String> {
+        let bytes = read_blob(self.readable()?).await.map_err(js_error)?;
+        String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
 
-        let future = wasm_bindgen_futures::JsFuture::from(promise);
+    /// Size of the file in bytes.
+    pub fn size(&self) -> io::Result<u64> {
+        Ok(self.readable()?.size() as u64)
+    }
 
-        let res = future.await.unwrap();
+    /// Time the file was last modified.
+    pub fn modified(&self) -> io::Result<SystemTime> {
+        let millis = self.readable()?.last_modified();
+        Ok(SystemTime::UNIX_EPOCH + Duration::from_millis(millis as u64))
+    }
 
-        let buffer: js_sys::Uint8Array = js_sys::Uint8Array::new(&res);
-        let mut vec = vec![0; buffer.length() as usize];
-        buffer.copy_to(&mut vec[..]);
+    /// MIME type of the file, like `image/png`, as the browser tells it.
+    pub async fn mime_type(&self) -> io::Result<String> {
+        let mime_type = self.readable()?.type_();
+        if mime_type.is_empty() {
+            Ok("application/octet-stream".to_owned())
+        } else {
+            Ok(mime_type)
+        }
+    }
 
-        vec
+    fn readable(&self) -> io::Result<&web_sys::File> {
+        match &self.0 {
+            WasmFileHandleKind::Readable(file) => Ok(file),
+            WasmFileHandleKind::Writable(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "this FileHandle is only writable, use `pick_file` to get a readable one",
+            )),
+        }
     }
 
     #[cfg(feature = "file-handle-inner")]
@@ -78,6 +148,136 @@ impl FileHandle {
     }
 }
 
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Called with the number of bytes read so far and the size of the file.
+type Progress = Box<dyn FnMut(u64, Option<u64>)>;
+
+type ChunkFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, JsValue>>>>;
+
+/// Reads a file in chunks, see [`FileHandle::reader`].
+///
+/// Every chunk is a `slice` of the file, read when the previous one was consumed.
+pub struct FileReader {
+    file: Option<web_sys::File>,
+    /// Returned once by a reader of a handle that is not readable.
+    error: Option<io::Error>,
+    chunk_size: usize,
+    progress: Option<Progress>,
+    pending: Option<ChunkFuture>,
+    bytes_read: u64,
+    leftover: Vec<u8>,
+    leftover_pos: usize,
+}
+
+impl FileReader {
+    /// Reads chunks of `chunk_size` bytes, 64 KiB by default. The last one can be shorter.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Calls `progress` with the number of bytes read so far and the size of the file, if known,
+    /// after every chunk.
+    pub fn on_progress(mut self, progress: impl FnMut(u64, Option<u64>) + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// The number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    fn poll_chunk(&mut self, ctx: &mut Context<'_>) -> Poll<Option<io::Result<Vec<u8>>>> {
+        let Some(file) = &self.file else {
+            return Poll::Ready(self.error.take().map(Err));
+        };
+
+        let total = file.size() as u64;
+        if self.pending.is_none() {
+            if self.bytes_read >= total {
+                return Poll::Ready(None);
+            }
+
+            let end = total.min(self.bytes_read + self.chunk_size as u64);
+            let blob = match file.slice_with_f64_and_f64(self.bytes_read as f64, end as f64) {
+                Ok(blob) => blob,
+                Err(err) => return Poll::Ready(Some(Err(js_error(err)))),
+            };
+            self.pending = Some(Box::pin(async move { read_blob(&blob).await }));
+        }
+
+        let res = match self.pending.as_mut().unwrap().as_mut().poll(ctx) {
+            Poll::Ready(res) => res,
+            Poll::Pending => return Poll::Pending,
+        };
+        self.pending = None;
+
+        let chunk = res.map_err(js_error)?;
+        self.bytes_read += chunk.len() as u64;
+        if let Some(progress) = &mut self.progress {
+            progress(self.bytes_read, Some(total));
+        }
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+impl futures_core::Stream for FileReader {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // Bytes left over by `AsyncRead` come first.
+        if this.leftover_pos < this.leftover.len() {
+            let leftover = this.leftover.split_off(this.leftover_pos);
+            this.leftover.clear();
+            this.leftover_pos = 0;
+            return Poll::Ready(Some(Ok(leftover)));
+        }
+
+        this.poll_chunk(ctx)
+    }
+}
+
+impl futures_io::AsyncRead for FileReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        ctx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        while this.leftover_pos == this.leftover.len() {
+            match this.poll_chunk(ctx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.leftover = chunk;
+                    this.leftover_pos = 0;
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err)),
+                Poll::Ready(None) => return Poll::Ready(Ok(0)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let leftover = &this.leftover[this.leftover_pos..];
+        let len = leftover.len().min(buf.len());
+        buf[..len].copy_from_slice(&leftover[..len]);
+        this.leftover_pos += len;
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl std::fmt::Debug for FileReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileReader")
+            .field("chunk_size", &self.chunk_size)
+            .field("bytes_read", &self.bytes_read)
+            .finish_non_exhaustive()
+    }
+}
+
 impl std::fmt::Debug for FileHandle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.file_name())
@@ -0,0 +1,303 @@
+//! Crash-safe writes: the new content goes to a temporary file next to the destination, which
+//! is renamed over it once everything was written and synced to disk.
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+/// The backup of `path` kept by [`FileWriter::keep_backup`], `path` with a `~` appended.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push("~");
+    backup.into()
+}
+
+/// A temporary file that replaces `path` when it is committed, and is removed otherwise.
+pub(crate) struct AtomicFile {
+    path: PathBuf,
+    temp: PathBuf,
+    file: File,
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        // The file a symbolic link points to is replaced, not the link.
+        let path = match fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(path)?,
+            _ => path.to_owned(),
+        };
+
+        if path.file_name().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a file path",
+            ));
+        }
+        // Not derived from the name of the destination, which can be as long as the file
+        // system allows.
+        let temp = path.with_file_name(format!(
+            ".rfd-{}-{}.tmp",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let file = File::options().write(true).create_new(true).open(&temp)?;
+        let atomic = Self {
+            path,
+            temp,
+            file,
+            committed: false,
+        };
+
+        if let Ok(meta) = fs::metadata(&atomic.path) {
+            atomic.file.set_permissions(meta.permissions())?;
+        }
+        Ok(atomic)
+    }
+
+    /// Syncs the temporary file and renames it over the destination, after linking the
+    /// destination to its backup path if `keep_backup` is set.
+    pub(crate) fn commit(mut self, keep_backup: bool) -> io::Result<()> {
+        self.file.sync_all()?;
+
+        if keep_backup && self.path.exists() {
+            let backup = backup_path(&self.path);
+            match fs::remove_file(&backup) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+            // A hard link keeps the destination in place until the rename, copying is for
+            // file systems without them.
+            if fs::hard_link(&self.path, &backup).is_err() {
+                fs::copy(&self.path, &backup)?;
+            }
+        }
+
+        fs::rename(&self.temp, &self.path)?;
+        self.committed = true;
+
+        // The rename itself is only durable once the folder is synced.
+        #[cfg(unix)]
+        if let Some(dir) = self.path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.temp);
+        }
+    }
+}
+
+enum Command {
+    Write(Vec<u8>),
+    Commit { keep_backup: bool },
+}
+
+#[derive(Default)]
+struct WriterShared {
+    waker: Option<Waker>,
+    /// The number of commands done by the thread.
+    done: u64,
+    /// The first error, every later write fails with it.
+    error: Option<io::Error>,
+}
+
+/// Writes a file in chunks, see [`FileHandle::create`](crate::FileHandle::create).
+///
+/// Writes go to a temporary file next to the destination, on a `std::thread`. Closing the writer
+/// with [`AsyncWriteExt::close`](https://docs.rs/futures/latest/futures/io/trait.AsyncWriteExt.html#method.close)
+/// syncs it to disk and renames it over the destination, dropping it before leaves the
+/// destination untouched.
+pub struct FileWriter {
+    commands: mpsc::SyncSender<Command>,
+    thread: Option<std::thread::JoinHandle<()>>,
+    shared: Arc<Mutex<WriterShared>>,
+    sent: u64,
+    keep_backup: bool,
+    closing: bool,
+}
+
+impl FileWriter {
+    pub(crate) fn create(path: PathBuf) -> Self {
+        // A bounded channel, so writers wait for the thread to catch up.
+        let (commands, received) = mpsc::sync_channel::<Command>(2);
+        let shared: Arc<Mutex<WriterShared>> = Default::default();
+
+        let state = shared.clone();
+        let thread = std::thread::Builder::new()
+            .name("rfd_file_write".into())
+            .spawn(move || {
+                let mut file = AtomicFile::create(&path);
+
+                // The loop ends when the writer is dropped, the file is removed if it was not
+                // committed by then.
+                while let Ok(command) = received.recv() {
+                    let res = match (command, &mut file) {
+                        (_, Err(err)) => Err(io::Error::new(err.kind(), err.to_string())),
+                        (Command::Write(bytes), Ok(file)) => file.write_all(&bytes),
+                        (Command::Commit { keep_backup }, file) => {
+                            let file = std::mem::replace(
+                                file,
+                                Err(io::Error::other("the file was already committed")),
+                            );
+                            file.and_then(|file| file.commit(keep_backup))
+                        }
+                    };
+
+                    let mut state = state.lock().unwrap();
+                    state.done += 1;
+                    if let Err(err) = res {
+                        state.error.get_or_insert(err);
+                    }
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                }
+            })
+            .unwrap();
+
+        Self {
+            commands,
+            thread: Some(thread),
+            shared,
+            sent: 0,
+            keep_backup: false,
+            closing: false,
+        }
+    }
+
+    /// Keeps the previous content of the destination as a backup when the writer is closed,
+    /// next to it with a `~` appended to its name.
+    pub fn keep_backup(mut self) -> Self {
+        self.keep_backup = true;
+        self
+    }
+
+    fn send(&mut self, ctx: &mut Context<'_>, command: Command) -> Poll<io::Result<()>> {
+        // The waker goes first, so a command done in between is not missed.
+        self.shared.lock().unwrap().waker = Some(ctx.waker().clone());
+        match self.commands.try_send(command) {
+            Ok(()) => {
+                self.sent += 1;
+                Poll::Ready(Ok(()))
+            }
+            Err(mpsc::TrySendError::Full(_)) => Poll::Pending,
+            Err(mpsc::TrySendError::Disconnected(_)) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the writer thread stopped",
+            ))),
+        }
+    }
+
+    /// Ready once every command sent was done, with the first error.
+    fn poll_done(&mut self, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut state = self.shared.lock().unwrap();
+        if let Some(err) = &state.error {
+            return Poll::Ready(Err(io::Error::new(err.kind(), err.to_string())));
+        }
+        if state.done < self.sent {
+            state.waker = Some(ctx.waker().clone());
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn error(&self) -> Option<io::Error> {
+        let state = self.shared.lock().unwrap();
+        let err = state.error.as_ref()?;
+        Some(io::Error::new(err.kind(), err.to_string()))
+    }
+}
+
+impl futures_io::AsyncWrite for FileWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        ctx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if let Some(err) = this.error() {
+            return Poll::Ready(Err(err));
+        }
+        if this.closing {
+            return Poll::Ready(Err(io::Error::other("the writer was closed")));
+        }
+
+        this.send(ctx, Command::Write(buf.to_vec()))
+            .map_ok(|()| buf.len())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_done(ctx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if !this.closing {
+            if let Some(err) = this.error() {
+                return Poll::Ready(Err(err));
+            }
+            let keep_backup = this.keep_backup;
+            match this.send(ctx, Command::Commit { keep_backup }) {
+                Poll::Ready(Ok(())) => this.closing = true,
+                other => return other,
+            }
+        }
+
+        this.poll_done(ctx)
+    }
+}
+
+impl Drop for FileWriter {
+    /// Waits for the thread to finish the writes sent to it and to remove the temporary file, if
+    /// the writer was not closed.
+    fn drop(&mut self) {
+        // Disconnecting the channel ends the thread.
+        let (disconnected, _) = mpsc::sync_channel(0);
+        drop(std::mem::replace(&mut self.commands, disconnected));
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for FileWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileWriter")
+            .field("keep_backup", &self.keep_backup)
+            .finish_non_exhaustive()
+    }
+}
@@ -40,10 +40,20 @@
 //! On Linux & BSDs, two backends are available, one using the [GTK3 Rust bindings](https://gtk-rs.org/)
 //! and the other using the [XDG Desktop Portal](https://github.com/flatpak/xdg-desktop-portal)
 //! D-Bus API through [ashpd](https://github.com/bilelmoussaoui/ashpd) &
-//! [zbus](https://gitlab.freedesktop.org/dbus/zbus/).
+//! [zbus](https://gitlab.freedesktop.org/dbus/zbus/). The `zenity` helper binary is always
+//! available as a fallback, and so is `kdialog` on KDE sessions. Without a display, eg. over SSH,
+//! dialogs are shown as prompts on the terminal when stdin is one.
+//!
+//! Both features can be enabled at once, the backend is then picked at runtime: the XDG Desktop
+//! Portal when it is running, otherwise kdialog on KDE sessions where it is installed, otherwise
+//! GTK3, otherwise zenity, otherwise the terminal. When a backend fails to show a dialog, the next
+//! one is tried. Applications can reorder or shorten that list with [linux::set_fallback_policy],
+//! or put a backend first with [linux::set_preferred_backend], and users with the `RFD_BACKEND`
+//! environment variable (`gtk3`, `portal`, `zenity`, `kdialog` or `terminal`). The choice is
+//! logged at the debug level.
 //!
 //! ## GTK backend
-//! The GTK backend is used when the `xdg-portal` feature is disabled with the [`default-features = false`](https://doc.rust-lang.org/cargo/reference/features.html#dependency-features), and `gtk3` is enabled instead. The GTK3
+//! The GTK backend is used with the `gtk3` Cargo feature, enable it with the [`default-features = false`](https://doc.rust-lang.org/cargo/reference/features.html#dependency-features) to build without the XDG Desktop Portal backend. The GTK3
 //! backend requires the C library and development headers to be installed to build RFD. The package
 //! names on various distributions are:
 //!
@@ -67,8 +77,47 @@
 //! D-Bus API that RFD requires (it does not interfere with the other portal implementations;
 //! they can all be installed simultaneously).
 //!
+//! In a sandbox such as Flatpak, the selected files are shared through the document portal, under
+//! `/run/user/<uid>/doc/`. [FileHandle::document_id] tells which document a file is, and with the
+//! `xdg-portal` feature `FileHandle::host_path` finds its path outside of the sandbox and
+//! `FileHandle::persist` keeps access to it after the application restarts.
+//!
 //! The XDG Desktop Portal has no API for message dialogs, so the [MessageDialog] and
-//! [AsyncMessageDialog] structs will not build with this backend.
+//! [AsyncMessageDialog] structs use kdialog on KDE sessions where it is installed, otherwise GTK3
+//! when it is enabled, and zenity otherwise.
+//!
+//! # Custom backends
+//!
+//! Dialogs can be routed through your own implementation of [DialogBackend], eg. an in-app
+//! file browser or a test double. Set it on a single dialog with [FileDialog::with_backend],
+//! or for every dialog of the process with [set_backend].
+//!
+//! The `testing` feature adds `rfd::testing::ScriptedBackend`, which answers dialogs from a
+//! script in unit tests.
+//!
+//! # Automation
+//!
+//! When the `RFD_AUTOMATION` environment variable names a JSON file, no dialog is shown. Every
+//! dialog of the process, custom backends included, is answered by the next entry of the array
+//! in that file instead, which lets end-to-end tests drive a real binary without a display:
+//!
+//! ```json
+//! [
+//!     { "dialog": "pick_file", "path": "/tmp/photo.png" },
+//!     { "dialog": "pick_files", "paths": ["/tmp/a.txt", "/tmp/b.txt"] },
+//!     { "dialog": "save_file", "cancel": true },
+//!     { "dialog": "message", "button": "Yes" }
+//! ]
+//! ```
+//!
+//! `dialog` is optional, when present the answer is only used for that kind of dialog
+//! (`pick_file`, `pick_files`, `pick_folder`, `pick_folders`, `save_file` or `message`).
+//! Message dialogs are answered with `Yes`, `No`, `Ok`, `Cancel` or the label of a custom button.
+//! A dialog without a matching answer fails with [Error::Automation].
+//!
+//! Set `RFD_AUTOMATION_TRANSCRIPT` to a file path to also record every dialog that was asked
+//! for, with its title, starting directory, file name, filters and button label, one JSON object
+//! per line.
 //!
 //! # macOS non-windowed applications, async, and threading
 //!
@@ -93,7 +142,7 @@
 //!
 //! # Cargo features
 //!  * `gtk3`: Uses GTK for dialogs on Linux & BSDs; has no effect on Windows and macOS
-//!  * `xdg-portal`: Uses XDG Desktop Portal instead of GTK on Linux & BSDs
+//!  * `xdg-portal`: Uses XDG Desktop Portal on Linux & BSDs, it can be combined with `gtk3`
 //!  * `common-controls-v6`: Use `TaskDialogIndirect` API from ComCtl32.dll v6 for showing message dialog. This is necessary if you need to customize dialog button texts.
 //!
 //! # State
@@ -125,15 +174,56 @@
 
 mod backend;
 
+mod error;
+pub use error::Error;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use backend::custom::{clear_backend, set_backend, DialogBackend};
+pub use backend::DialogFutureType;
+
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
+
+/// Linux & BSD specific settings.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub mod linux {
+    pub use crate::backend::linux::{
+        set_fallback_policy, set_preferred_backend, FallbackPolicy, LinuxBackend,
+    };
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub mod recent;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod persistence;
+
+mod dialog_handle;
+pub use dialog_handle::DialogHandle;
+
 mod file_handle;
-pub use file_handle::FileHandle;
+#[cfg(not(target_arch = "wasm32"))]
+pub use file_handle::FileWriter;
+pub use file_handle::{FileHandle, FileReader};
 
 mod file_dialog;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use file_dialog::FileDialog;
 
-pub use file_dialog::AsyncFileDialog;
+pub use file_dialog::{AsyncFileDialog, Choice, FileResponse, Filter, FilterPattern};
 
 mod message_dialog;
 pub use message_dialog::{
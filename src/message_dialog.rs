@@ -1,6 +1,16 @@
+use crate::backend::log_error_async;
 use crate::backend::AsyncMessageDialogImpl;
 use crate::backend::MessageDialogImpl;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::{
+    custom::{CustomBackend, DialogBackend},
+    log_error,
+};
+use crate::{DialogHandle, Error};
 use std::fmt::{Display, Formatter};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use std::time::Duration;
 
 use std::future::Future;
 
@@ -19,6 +29,10 @@ pub struct MessageDialog {
     pub(crate) buttons: MessageButtons,
     pub(crate) parent: Option<RawWindowHandle>,
     pub(crate) parent_display: Option<RawDisplayHandle>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) backend: Option<CustomBackend>,
+    pub(crate) handle: Option<DialogHandle>,
+    pub(crate) timeout: Option<Duration>,
 }
 
 // Oh god, I don't like sending RawWindowHandle between threads but here we go anyways...
@@ -64,6 +78,18 @@ impl MessageDialog {
         self
     }
 
+    /// Dismiss the dialog if the user did not answer it within `timeout`.
+    ///
+    /// [`MessageDialog::try_show`] then returns [`Error::TimedOut`], and [`MessageDialog::show`]
+    /// returns [`MessageDialogResult::Cancel`]. Supported platforms:
+    ///  * Linux
+    ///
+    /// Other platforms ignore the timeout of synchronous dialogs.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     /// Set parent windows explicitly (optional).
     /// Supported platforms:
     ///  * Windows
@@ -75,10 +101,60 @@ impl MessageDialog {
         self
     }
 
+    /// Show this dialog with a custom [`DialogBackend`] instead of the native one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_backend(mut self, backend: Arc<dyn DialogBackend>) -> Self {
+        self.backend = Some(CustomBackend(backend));
+        self
+    }
+
     /// Shows a message dialog and returns the button that was pressed.
     pub fn show(self) -> MessageDialogResult {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(backend) = CustomBackend::resolve(&self.backend) {
+            return log_error("show", backend.show_message(&self));
+        }
         MessageDialogImpl::show(self)
     }
+
+    /// Shows a message dialog and returns the button that was pressed.
+    ///
+    /// Unlike [`MessageDialog::show`], it returns an [`Error`] when the dialog could not be shown.
+    pub fn try_show(self) -> Result<MessageDialogResult, Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(backend) = CustomBackend::resolve(&self.backend) {
+            return backend.show_message(&self);
+        }
+        MessageDialogImpl::try_show(self)
+    }
+}
+
+/// Getters for the options set on the builder, meant for [`DialogBackend`] implementations.
+impl MessageDialog {
+    /// Level set with [`MessageDialog::set_level`]
+    pub fn level(&self) -> MessageLevel {
+        self.level
+    }
+
+    /// Title set with [`MessageDialog::set_title`]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Description set with [`MessageDialog::set_description`]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Buttons set with [`MessageDialog::set_buttons`]
+    pub fn buttons(&self) -> &MessageButtons {
+        &self.buttons
+    }
+
+    /// Value set with [`MessageDialog::set_timeout`]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 }
 
 /// Asynchronous Message Dialog. Supported platforms:
@@ -128,6 +204,19 @@ impl AsyncMessageDialog {
         self
     }
 
+    /// Dismiss the dialog if the user did not answer it within `timeout`.
+    ///
+    /// [`AsyncMessageDialog::try_show`] then resolves to [`Error::TimedOut`], and
+    /// [`AsyncMessageDialog::show`] to [`MessageDialogResult::Cancel`]. Supported platforms:
+    ///  * Linux
+    ///  * Windows and Mac: the future resolves, but the native dialog stays open
+    ///
+    /// WASM32 ignores the timeout, there are no threads to run the timer on.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.0 = self.0.set_timeout(timeout);
+        self
+    }
+
     /// Set parent windows explicitly (optional).
     /// Supported platforms:
     ///  * Windows
@@ -138,27 +227,51 @@ impl AsyncMessageDialog {
         self
     }
 
+    /// Show this dialog with a custom [`DialogBackend`] instead of the native one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_backend(mut self, backend: Arc<dyn DialogBackend>) -> Self {
+        self.0 = self.0.with_backend(backend);
+        self
+    }
+
+    /// Attach this dialog to `handle`, closing the handle dismisses the dialog.
+    pub fn with_handle(mut self, handle: &DialogHandle) -> Self {
+        self.0.handle = Some(handle.clone());
+        self
+    }
+
     /// Shows a message dialog and returns the button that was pressed.
     pub fn show(self) -> impl Future<Output = MessageDialogResult> {
-        AsyncMessageDialogImpl::show_async(self.0)
+        log_error_async("show", Box::pin(self.try_show()))
+    }
+
+    /// Shows a message dialog and returns the button that was pressed.
+    ///
+    /// Unlike [`AsyncMessageDialog::show`], it resolves to an [`Error`] when the dialog could not be shown.
+    pub fn try_show(self) -> impl Future<Output = Result<MessageDialogResult, Error>> {
+        let handle = self.0.handle.clone();
+        let timeout = self.0.timeout;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(backend) = CustomBackend::resolve(&self.0.backend) {
+            let future = backend.show_message_async(self.0);
+            return DialogHandle::attach(handle, timeout, future, || MessageDialogResult::Cancel);
+        }
+        let future = AsyncMessageDialogImpl::try_show_async(self.0);
+        DialogHandle::attach(handle, timeout, future, || MessageDialogResult::Cancel)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub enum MessageLevel {
+    #[default]
     Info,
     Warning,
     Error,
 }
 
-impl Default for MessageLevel {
-    fn default() -> Self {
-        Self::Info
-    }
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone)]
 pub enum MessageButtons {
+    #[default]
     Ok,
     OkCancel,
     YesNo,
@@ -174,12 +287,6 @@ pub enum MessageButtons {
     YesNoCancelCustom(String, String, String),
 }
 
-impl Default for MessageButtons {
-    fn default() -> Self {
-        Self::Ok
-    }
-}
-
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum MessageDialogResult {
     Yes,
@@ -0,0 +1,295 @@
+//! Where file dialogs were left, per persistence key, see [`FileDialog::set_persistence_key`].
+
+use crate::file_dialog::Filter;
+use crate::{FileDialog, FileHandle, FileResponse};
+
+use serde_json::{json, Map, Value};
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// The result of a file dialog, as far as rfd's bookkeeping is concerned.
+pub(crate) trait Selection {
+    /// The selected paths, empty if the dialog was cancelled.
+    fn paths(&self) -> Vec<&Path>;
+
+    /// The first selected path, `None` if the dialog was cancelled.
+    fn first_path(&self) -> Option<&Path> {
+        self.paths().first().copied()
+    }
+
+    /// The filter selected in the dialog, if the backend tells.
+    fn selected_filter(&self) -> Option<&Filter> {
+        None
+    }
+}
+
+impl Selection for PathBuf {
+    fn paths(&self) -> Vec<&Path> {
+        vec![self.as_path()]
+    }
+}
+
+impl Selection for Vec<PathBuf> {
+    fn paths(&self) -> Vec<&Path> {
+        self.iter().map(PathBuf::as_path).collect()
+    }
+}
+
+impl Selection for FileHandle {
+    fn paths(&self) -> Vec<&Path> {
+        vec![self.path()]
+    }
+}
+
+impl Selection for Vec<FileHandle> {
+    fn paths(&self) -> Vec<&Path> {
+        self.iter().map(FileHandle::path).collect()
+    }
+}
+
+impl<T: Selection> Selection for Option<T> {
+    fn paths(&self) -> Vec<&Path> {
+        self.as_ref().map(T::paths).unwrap_or_default()
+    }
+
+    fn selected_filter(&self) -> Option<&Filter> {
+        self.as_ref()?.selected_filter()
+    }
+}
+
+impl<T: Selection> Selection for FileResponse<T> {
+    fn paths(&self) -> Vec<&Path> {
+        self.selection.paths()
+    }
+
+    fn selected_filter(&self) -> Option<&Filter> {
+        self.selected_filter.as_ref()
+    }
+}
+
+/// The directory rfd keeps its state in.
+fn state_dir() -> Option<PathBuf> {
+    let absolute = |path: PathBuf| path.is_absolute().then_some(path);
+
+    #[cfg(target_os = "windows")]
+    let dir = std::env::var_os("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .and_then(absolute);
+
+    #[cfg(target_os = "macos")]
+    let dir = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/Application Support"))
+        .and_then(absolute);
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .and_then(absolute)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".local/state"))
+                .and_then(absolute)
+        });
+
+    dir.map(|dir| dir.join("rfd"))
+}
+
+/// The file the state of this application is kept in, one per executable.
+fn default_store() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let app = exe.file_stem()?.to_string_lossy();
+    Some(state_dir()?.join(format!("{app}.json")))
+}
+
+fn load(store: &Path) -> Map<String, Value> {
+    match std::fs::read_to_string(store) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(Value::Object(entries)) => entries,
+            _ => {
+                log::warn!("Ignoring the invalid dialog state in {store:?}");
+                Map::new()
+            }
+        },
+        Err(_) => Map::new(),
+    }
+}
+
+fn save(store: &Path, entries: &Map<String, Value>) -> std::io::Result<()> {
+    if let Some(dir) = store.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    // Write to a temporary file first, so a crash can't leave a truncated file behind.
+    let tmp = store.with_extension("json.tmp");
+    std::fs::write(&tmp, Value::Object(entries.clone()).to_string())?;
+    std::fs::rename(tmp, store)
+}
+
+/// `path` as JSON: a string, or on Unix an array of bytes when it is not valid UTF-8. Other
+/// paths that are not valid UTF-8 are not remembered.
+fn path_to_json(path: &OsStr) -> Value {
+    match path.to_str() {
+        Some(path) => Value::from(path),
+        #[cfg(unix)]
+        None => Value::from(std::os::unix::ffi::OsStrExt::as_bytes(path)),
+        #[cfg(not(unix))]
+        None => Value::Null,
+    }
+}
+
+/// A path stored by [`path_to_json`].
+fn path_from_json(value: &Value) -> Option<OsString> {
+    match value {
+        Value::String(path) => Some(path.into()),
+        #[cfg(unix)]
+        Value::Array(bytes) => {
+            let bytes = bytes
+                .iter()
+                .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect::<Option<Vec<u8>>>()?;
+            Some(std::os::unix::ffi::OsStringExt::from_vec(bytes))
+        }
+        _ => None,
+    }
+}
+
+/// What to remember once a dialog with a persistence key was answered.
+pub(crate) struct Persistence {
+    store: PathBuf,
+    key: String,
+    filters: Vec<Filter>,
+    save_dialog: bool,
+}
+
+impl Persistence {
+    /// Fills in the options of `dialog` that the application left unset with the ones
+    /// remembered for its persistence key.
+    pub fn restore(dialog: &mut FileDialog, save_dialog: bool) -> Option<Self> {
+        let key = dialog.persistence_key.clone()?;
+        let store = default_store()?;
+        Some(Self::restore_from(store, key, dialog, save_dialog))
+    }
+
+    fn restore_from(
+        store: PathBuf,
+        key: String,
+        dialog: &mut FileDialog,
+        save_dialog: bool,
+    ) -> Self {
+        let entry = load(&store).remove(&key).unwrap_or_default();
+
+        if dialog.starting_directory.is_none() {
+            dialog.starting_directory = path_from_json(&entry["directory"])
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_dir());
+        }
+
+        if dialog.file_name.is_none() && save_dialog {
+            dialog.file_name = path_from_json(&entry["file_name"]);
+        }
+
+        if dialog.current_filter.is_none() {
+            dialog.current_filter = entry["filter"]
+                .as_str()
+                .filter(|name| dialog.filters.iter().any(|f| f.name == *name))
+                .map(String::from);
+        }
+
+        Self {
+            store,
+            key,
+            filters: dialog.filters.clone(),
+            save_dialog,
+        }
+    }
+
+    /// Remembers where the user left the dialog, if it was not cancelled.
+    pub fn remember(&self, selection: &impl Selection) {
+        let Some(path) = selection.first_path() else {
+            return;
+        };
+
+        let mut entries = load(&self.store);
+        entries.insert(
+            self.key.clone(),
+            json!({
+                "directory": path.parent().map(|dir| path_to_json(dir.as_os_str())),
+                "file_name": self
+                    .save_dialog
+                    .then(|| path.file_name().map(path_to_json))
+                    .flatten(),
+                // Backends that don't tell the selected filter, the first one matching is a good guess.
+                "filter": selection
+                    .selected_filter()
+                    .or_else(|| self.filters.iter().find(|filter| filter.matches(path)))
+                    .map(|filter| &filter.name),
+            }),
+        );
+
+        if let Err(err) = save(&self.store, &entries) {
+            log::warn!("Failed to save the dialog state to {:?}: {err}", self.store);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dir = std::env::temp_dir().join(format!("rfd-persistence-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = dir.join("state.json");
+
+        let mut dialog = FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .add_filter("Image", &["png"]);
+        let persistence =
+            Persistence::restore_from(store.clone(), "export".into(), &mut dialog, true);
+        assert_eq!(dialog.starting_directory, None);
+        persistence.remember(&Some(dir.join("photo.PNG")));
+
+        let mut dialog = FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .add_filter("Image", &["png"]);
+        Persistence::restore_from(store.clone(), "export".into(), &mut dialog, true);
+        assert_eq!(dialog.starting_directory.as_deref(), Some(dir.as_path()));
+        assert_eq!(dialog.file_name.as_deref(), Some("photo.PNG".as_ref()));
+        assert_eq!(dialog.current_filter.as_deref(), Some("Image"));
+
+        let mut dialog = FileDialog::new().set_directory("/");
+        Persistence::restore_from(store, "other".into(), &mut dialog, false);
+        assert_eq!(dialog.starting_directory.as_deref(), Some(Path::new("/")));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir =
+            std::env::temp_dir().join(format!("rfd-persistence-bytes-{}", std::process::id()));
+        let latin1 = dir.join(OsStr::from_bytes(b"caf\xe9"));
+        std::fs::create_dir_all(&latin1).unwrap();
+        let store = dir.join("state.json");
+
+        let mut dialog = FileDialog::new();
+        let persistence =
+            Persistence::restore_from(store.clone(), "export".into(), &mut dialog, true);
+        persistence.remember(&Some(latin1.join(OsStr::from_bytes(b"r\xe9sum\xe9.txt"))));
+
+        let mut dialog = FileDialog::new();
+        Persistence::restore_from(store, "export".into(), &mut dialog, true);
+        assert_eq!(dialog.starting_directory.as_deref(), Some(latin1.as_path()));
+        assert_eq!(
+            dialog.file_name.as_deref(),
+            Some(OsStr::from_bytes(b"r\xe9sum\xe9.txt"))
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,436 @@
+//! The freedesktop list of recently used files, see [`FileDialog::set_add_to_recent`](crate::FileDialog::set_add_to_recent).
+//!
+//! It is shared by every application of the desktop, and kept in
+//! `$XDG_DATA_HOME/recently-used.xbel`.
+//!
+//! ```no_run
+//! for file in rfd::recent::list() {
+//!     println!("{}: {}", file.path().display(), file.mime_type());
+//! }
+//! ```
+
+use crate::backend::linux::mime;
+
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BOOKMARK_NS: &str = "http://www.freedesktop.org/standards/desktop-bookmarks";
+const MIME_NS: &str = "http://www.freedesktop.org/standards/shared-mime-info";
+
+/// A file of the recently used files list.
+#[derive(Debug, Clone)]
+pub struct RecentFile {
+    path: PathBuf,
+    mime_type: String,
+    modified: SystemTime,
+}
+
+impl RecentFile {
+    /// Path of the file, it may not exist anymore
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// MIME type of the file, eg. `image/png`
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// When the file was last opened or saved
+    pub fn modified(&self) -> SystemTime {
+        self.modified
+    }
+}
+
+/// The local files of the recently used files list, most recent first.
+///
+/// Returns an empty list if there is none, or if it can't be read.
+pub fn list() -> Vec<RecentFile> {
+    let Some(store) = store() else {
+        return Vec::new();
+    };
+
+    match read_store(&store) {
+        Ok(content) => list_from(&content).unwrap_or_else(|err| {
+            log::warn!("Ignoring the invalid recently used files list {store:?}: {err}");
+            Vec::new()
+        }),
+        Err(err) => {
+            log::warn!("Failed to read the recently used files list {store:?}: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Adds `paths` to the recently used files list, as opened or saved by this application.
+pub(crate) fn add(paths: &[&Path]) {
+    let Some(store) = store() else {
+        return;
+    };
+
+    let res = read_store(&store).and_then(|content| {
+        let content = add_to(&content, &application(), paths, SystemTime::now())?;
+        write_store(&store, &content)
+    });
+
+    if let Err(err) = res {
+        log::warn!("Failed to add to the recently used files list {store:?}: {err}");
+    }
+}
+
+fn store() -> Option<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".local/share")))?;
+
+    Some(data_dir.join("recently-used.xbel"))
+}
+
+fn read_store(store: &Path) -> io::Result<String> {
+    match std::fs::read_to_string(store) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        res => res,
+    }
+}
+
+fn write_store(store: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = store.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    // Other applications read the list at any time, never let them see half of it.
+    let tmp = store.with_extension("xbel.rfd-tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(tmp, store)
+}
+
+/// The name this application is registered under, the name of its executable.
+fn application() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "rfd".to_owned())
+}
+
+fn xml_error(err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+fn attribute(element: &BytesStart, name: &str) -> io::Result<Option<String>> {
+    match element.try_get_attribute(name).map_err(xml_error)? {
+        Some(attr) => Ok(Some(attr.unescape_value().map_err(xml_error)?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+/// Copies `element` with the attributes in `replace` set to new values.
+fn with_attributes(
+    element: &BytesStart,
+    replace: &[(&str, &str)],
+) -> io::Result<BytesStart<'static>> {
+    let mut copy = BytesStart::new(String::from_utf8_lossy(element.name().as_ref()).into_owned());
+
+    for attr in element.attributes() {
+        let attr = attr.map_err(xml_error)?;
+        if !replace
+            .iter()
+            .any(|(name, _)| name.as_bytes() == attr.key.as_ref())
+        {
+            copy.push_attribute(attr);
+        }
+    }
+    for attr in replace {
+        copy.push_attribute(*attr);
+    }
+
+    Ok(copy)
+}
+
+fn list_from(content: &str) -> io::Result<Vec<RecentFile>> {
+    let mut reader = Reader::from_str(content);
+    let mut files = Vec::new();
+    let mut current: Option<RecentFile> = None;
+
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(element) if element.name().as_ref() == b"bookmark" => {
+                let path = attribute(&element, "href")?.and_then(|href| path_from_uri(&href));
+                let modified = attribute(&element, "modified")?
+                    .and_then(|time| parse_time(&time))
+                    .unwrap_or(UNIX_EPOCH);
+
+                current = path.map(|path| RecentFile {
+                    path,
+                    mime_type: String::new(),
+                    modified,
+                });
+            }
+            Event::Empty(element) if element.name().as_ref() == b"mime:mime-type" => {
+                if let Some(file) = current.as_mut() {
+                    file.mime_type = attribute(&element, "type")?.unwrap_or_default();
+                }
+            }
+            Event::End(element) if element.name().as_ref() == b"bookmark" => {
+                files.extend(current.take());
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+    Ok(files)
+}
+
+/// Returns `content` with `paths` added to it, or marked as used again if they already are.
+fn add_to(content: &str, app: &str, paths: &[&Path], now: SystemTime) -> io::Result<String> {
+    let content = if content.trim().is_empty() {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xbel version=\"1.0\"\n      \
+             xmlns:bookmark=\"{BOOKMARK_NS}\"\n      xmlns:mime=\"{MIME_NS}\"\n>\n</xbel>\n"
+        )
+    } else {
+        content.to_owned()
+    };
+
+    let now = format_time(now);
+    let uris: Vec<String> = paths.iter().map(|path| uri_from_path(path)).collect();
+    let mut found = vec![false; uris.len()];
+
+    let mut reader = Reader::from_str(&content);
+    let mut writer = Writer::new(Vec::new());
+    // Whether we are in the bookmark of one of `paths`, and already saw this application in it.
+    let mut in_bookmark: Option<bool> = None;
+
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        match &event {
+            Event::Start(element) if element.name().as_ref() == b"bookmark" => {
+                let href = attribute(element, "href")?;
+                if let Some(i) = uris.iter().position(|uri| Some(uri) == href.as_ref()) {
+                    found[i] = true;
+                    in_bookmark = Some(false);
+
+                    let element =
+                        with_attributes(element, &[("modified", &now), ("visited", &now)])?;
+                    writer
+                        .write_event(Event::Start(element))
+                        .map_err(xml_error)?;
+                    continue;
+                }
+            }
+            Event::Empty(element)
+                if element.name().as_ref() == b"bookmark:application"
+                    && in_bookmark == Some(false)
+                    && attribute(element, "name")?.as_deref() == Some(app) =>
+            {
+                in_bookmark = Some(true);
+
+                let count = attribute(element, "count")?
+                    .and_then(|count| count.parse::<u32>().ok())
+                    .unwrap_or(0);
+                let count = (count + 1).to_string();
+                let element = with_attributes(element, &[("modified", &now), ("count", &count)])?;
+                writer
+                    .write_event(Event::Empty(element))
+                    .map_err(xml_error)?;
+                continue;
+            }
+            Event::End(element)
+                if element.name().as_ref() == b"bookmark:applications"
+                    && in_bookmark == Some(false) =>
+            {
+                write_application(&mut writer, app, &now).map_err(xml_error)?;
+            }
+            Event::End(element) if element.name().as_ref() == b"bookmark" => {
+                in_bookmark = None;
+            }
+            Event::End(element) if element.name().as_ref() == b"xbel" => {
+                for ((path, uri), found) in paths.iter().zip(&uris).zip(&found) {
+                    if !found {
+                        write_bookmark(&mut writer, uri, &mime::mime_type(path), app, &now)
+                            .map_err(xml_error)?;
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+
+        writer.write_event(event).map_err(xml_error)?;
+    }
+
+    String::from_utf8(writer.into_inner()).map_err(xml_error)
+}
+
+fn write_application(writer: &mut Writer<Vec<u8>>, app: &str, now: &str) -> quick_xml::Result<()> {
+    let exec = format!("'{app} %u'");
+    writer
+        .create_element("bookmark:application")
+        .with_attributes([
+            ("name", app),
+            ("exec", &exec),
+            ("modified", now),
+            ("count", "1"),
+        ])
+        .write_empty()?;
+    Ok(())
+}
+
+fn write_bookmark(
+    writer: &mut Writer<Vec<u8>>,
+    uri: &str,
+    mime_type: &str,
+    app: &str,
+    now: &str,
+) -> quick_xml::Result<()> {
+    writer.write_event(Event::Text(BytesText::new("  ")))?;
+    writer
+        .create_element("bookmark")
+        .with_attributes([
+            ("href", uri),
+            ("added", now),
+            ("modified", now),
+            ("visited", now),
+        ])
+        .write_inner_content(|writer| {
+            writer.write_event(Event::Start(BytesStart::new("info")))?;
+            writer
+                .create_element("metadata")
+                .with_attribute(("owner", "http://freedesktop.org"))
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("mime:mime-type")
+                        .with_attribute(("type", mime_type))
+                        .write_empty()?;
+                    writer.write_event(Event::Start(BytesStart::new("bookmark:applications")))?;
+                    write_application(writer, app, now)?;
+                    writer.write_event(Event::End(BytesEnd::new("bookmark:applications")))
+                })?;
+            writer.write_event(Event::End(BytesEnd::new("info")))
+        })?;
+    writer.write_event(Event::Text(BytesText::new("\n")))
+}
+
+fn uri_from_path(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        if encoded[i] == b'%' {
+            let hex = std::str::from_utf8(encoded.get(i + 1..i + 3)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            bytes.push(encoded[i]);
+            i += 1;
+        }
+    }
+
+    // Only local files, `file://host/path` URIs are not.
+    bytes
+        .starts_with(b"/")
+        .then(|| PathBuf::from(OsStr::from_bytes(&bytes)))
+}
+
+/// Formats `time` as an ISO 8601 UTC date, eg. `2024-05-01T10:00:00Z`.
+fn format_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // Days since the epoch to a civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Parses the dates written by [`format_time`], and the ones with fractional seconds written by GLib.
+fn parse_time(time: &str) -> Option<SystemTime> {
+    let field = |range: std::ops::Range<usize>| time.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hours, minutes, seconds) = (field(11..13)?, field(14..16)?, field(17..19)?);
+
+    // The inverse of `format_time`.
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let secs = days * 86400 + hours * 3600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_and_list() {
+        let other = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <xbel version=\"1.0\" xmlns:bookmark=\"http://www.freedesktop.org/standards/desktop-bookmarks\" \
+            xmlns:mime=\"http://www.freedesktop.org/standards/shared-mime-info\">\n  \
+            <bookmark href=\"file:///tmp/other.txt\" added=\"2020-01-01T00:00:00.000000Z\" \
+            modified=\"2020-01-01T00:00:00.000000Z\" visited=\"2020-01-01T00:00:00.000000Z\">\
+            <info><metadata owner=\"http://freedesktop.org\"><mime:mime-type type=\"text/plain\"/>\
+            <bookmark:applications><bookmark:application name=\"gedit\" exec=\"&apos;gedit %u&apos;\" \
+            modified=\"2020-01-01T00:00:00Z\" count=\"3\"/></bookmark:applications></metadata></info>\
+            </bookmark>\n</xbel>\n";
+
+        let then = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let path = Path::new("/tmp/a b.txt");
+        let content = add_to(other, "app", &[path], then).unwrap();
+        let content = add_to(&content, "app", &[path], then + Duration::from_secs(60)).unwrap();
+        let content = add_to(&content, "gedit", &[Path::new("/tmp/other.txt")], then).unwrap();
+
+        let files = list_from(&content).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path(), path);
+        assert_eq!(files[0].modified(), then + Duration::from_secs(60));
+        assert_eq!(files[1].path(), Path::new("/tmp/other.txt"));
+        assert_eq!(files[1].mime_type(), "text/plain");
+
+        assert!(content.contains("href=\"file:///tmp/a%20b.txt\""));
+        assert!(content.contains("name=\"app\" exec=\"&apos;app %u&apos;\" modified=\"2023-11-14T22:14:20Z\" count=\"2\""));
+        assert!(content.contains("name=\"gedit\" exec=\"&apos;gedit %u&apos;\" modified=\"2023-11-14T22:13:20Z\" count=\"4\""));
+    }
+}
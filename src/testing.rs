@@ -0,0 +1,309 @@
+//! Scripted dialog backend for unit tests.
+//!
+//! [`ScriptedBackend`] answers dialogs from a queue of canned answers instead of showing them,
+//! and records every dialog it was asked to show, so tests can check the options the code
+//! under test used.
+//!
+//! ```
+//! use rfd::testing::{Answer, RequestKind, ScriptedBackend};
+//! use rfd::{FileDialog, MessageDialog, MessageDialogResult};
+//!
+//! let backend = ScriptedBackend::new();
+//! backend
+//!     .expect(RequestKind::PickFile, Answer::path("/tmp/photo.png"))
+//!     .expect(RequestKind::Message, Answer::Button(MessageDialogResult::Yes));
+//!
+//! let file = FileDialog::new()
+//!     .add_filter("image", &["png"])
+//!     .with_backend(backend.clone())
+//!     .pick_file();
+//! assert_eq!(file, Some("/tmp/photo.png".into()));
+//!
+//! let res = MessageDialog::new()
+//!     .set_title("Overwrite?")
+//!     .with_backend(backend.clone())
+//!     .show();
+//! assert_eq!(res, MessageDialogResult::Yes);
+//!
+//! let requests = backend.finish();
+//! assert_eq!(requests[0].file_dialog().unwrap().filters()[0].name(), "image");
+//! assert_eq!(requests[1].message_dialog().unwrap().title(), "Overwrite?");
+//! ```
+
+use crate::backend::custom::DialogBackend;
+use crate::{Error, FileDialog, MessageDialog, MessageDialogResult};
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// The dialog function that was called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    PickFile,
+    PickFiles,
+    PickFolder,
+    PickFolders,
+    SaveFile,
+    Message,
+}
+
+impl Display for RequestKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::PickFile => "pick_file",
+            Self::PickFiles => "pick_files",
+            Self::PickFolder => "pick_folder",
+            Self::PickFolders => "pick_folders",
+            Self::SaveFile => "save_file",
+            Self::Message => "message dialog",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Canned answer to a dialog.
+#[derive(Debug)]
+pub enum Answer {
+    /// The user picked these paths. Single selection dialogs only use the first one.
+    Paths(Vec<PathBuf>),
+    /// The user cancelled the dialog.
+    Cancel,
+    /// The user pressed this button of a message dialog.
+    Button(MessageDialogResult),
+    /// The dialog could not be shown.
+    Error(Error),
+}
+
+impl Answer {
+    /// The user picked `path`.
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        Self::Paths(vec![path.into()])
+    }
+}
+
+/// A dialog the [`ScriptedBackend`] was asked to show.
+#[derive(Debug, Clone)]
+pub struct Request {
+    kind: RequestKind,
+    dialog: RequestedDialog,
+}
+
+#[derive(Debug, Clone)]
+enum RequestedDialog {
+    File(FileDialog),
+    Message(MessageDialog),
+}
+
+impl Request {
+    /// The dialog function that was called.
+    pub fn kind(&self) -> RequestKind {
+        self.kind
+    }
+
+    /// The file dialog builder, for all kinds but [`RequestKind::Message`].
+    pub fn file_dialog(&self) -> Option<&FileDialog> {
+        match &self.dialog {
+            RequestedDialog::File(dialog) => Some(dialog),
+            RequestedDialog::Message(_) => None,
+        }
+    }
+
+    /// The message dialog builder, for [`RequestKind::Message`].
+    pub fn message_dialog(&self) -> Option<&MessageDialog> {
+        match &self.dialog {
+            RequestedDialog::File(_) => None,
+            RequestedDialog::Message(dialog) => Some(dialog),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    expected: VecDeque<(RequestKind, Answer)>,
+    requests: Vec<Request>,
+}
+
+/// A [`DialogBackend`] answering dialogs from a script.
+///
+/// Every dialog must have been announced with [`ScriptedBackend::expect`], in order. A dialog
+/// that was not expected, or of another kind than the next expected one, panics, failing the
+/// test.
+#[derive(Default)]
+pub struct ScriptedBackend {
+    state: Mutex<State>,
+}
+
+impl ScriptedBackend {
+    pub fn new() -> Arc<Self> {
+        Default::default()
+    }
+
+    /// Answer the next dialog, which must be of the given `kind`, with `answer`.
+    pub fn expect(&self, kind: RequestKind, answer: Answer) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .expected
+            .push_back((kind, answer));
+        self
+    }
+
+    /// Route every dialog of the process through this backend, see [`crate::set_backend`].
+    ///
+    /// Tests run in parallel by default, prefer passing the backend to the dialogs
+    /// with `with_backend` when you can.
+    pub fn install(self: &Arc<Self>) {
+        crate::set_backend(self.clone());
+    }
+
+    /// Dialogs shown so far, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Checks that every expected dialog was shown, and returns all of them.
+    ///
+    /// # Panics
+    /// If some expected dialogs were not shown.
+    pub fn finish(&self) -> Vec<Request> {
+        let state = self.state.lock().unwrap();
+        let missing: Vec<_> = state.expected.iter().map(|(kind, _)| kind).collect();
+        assert!(
+            missing.is_empty(),
+            "expected dialogs were not shown: {missing:?}"
+        );
+        state.requests.clone()
+    }
+
+    fn answer(&self, kind: RequestKind, dialog: RequestedDialog) -> Answer {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(Request { kind, dialog });
+
+        match state.expected.pop_front() {
+            Some((expected, answer)) if expected == kind => answer,
+            Some((expected, _)) => {
+                drop(state);
+                panic!("unexpected {kind}, the next expected dialog is a {expected}")
+            }
+            None => {
+                drop(state);
+                panic!("unexpected {kind}, no more dialogs were expected")
+            }
+        }
+    }
+
+    fn answer_paths(
+        &self,
+        kind: RequestKind,
+        dialog: &FileDialog,
+    ) -> Result<Option<Vec<PathBuf>>, Error> {
+        match self.answer(kind, RequestedDialog::File(dialog.clone())) {
+            Answer::Paths(paths) => Ok(Some(paths)),
+            Answer::Cancel => Ok(None),
+            Answer::Error(err) => Err(err),
+            Answer::Button(button) => panic!("{kind} can't be answered with {button}"),
+        }
+    }
+
+    fn answer_path(
+        &self,
+        kind: RequestKind,
+        dialog: &FileDialog,
+    ) -> Result<Option<PathBuf>, Error> {
+        self.answer_paths(kind, dialog)
+            .map(|paths| paths.and_then(|paths| paths.into_iter().next()))
+    }
+}
+
+impl DialogBackend for ScriptedBackend {
+    fn pick_file(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error> {
+        self.answer_path(RequestKind::PickFile, dialog)
+    }
+
+    fn pick_files(&self, dialog: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error> {
+        self.answer_paths(RequestKind::PickFiles, dialog)
+    }
+
+    fn pick_folder(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error> {
+        self.answer_path(RequestKind::PickFolder, dialog)
+    }
+
+    fn pick_folders(&self, dialog: &FileDialog) -> Result<Option<Vec<PathBuf>>, Error> {
+        self.answer_paths(RequestKind::PickFolders, dialog)
+    }
+
+    fn save_file(&self, dialog: &FileDialog) -> Result<Option<PathBuf>, Error> {
+        self.answer_path(RequestKind::SaveFile, dialog)
+    }
+
+    fn show_message(&self, dialog: &MessageDialog) -> Result<MessageDialogResult, Error> {
+        let kind = RequestKind::Message;
+        match self.answer(kind, RequestedDialog::Message(dialog.clone())) {
+            Answer::Button(button) => Ok(button),
+            Answer::Cancel => Ok(MessageDialogResult::Cancel),
+            Answer::Error(err) => Err(err),
+            Answer::Paths(paths) => panic!("a {kind} can't be answered with {paths:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsyncFileDialog, MessageButtons};
+
+    #[test]
+    fn records_requests() {
+        let backend = ScriptedBackend::new();
+        backend
+            .expect(RequestKind::SaveFile, Answer::Cancel)
+            .expect(
+                RequestKind::PickFiles,
+                Answer::Paths(vec!["/a".into(), "/b".into()]),
+            )
+            .expect(RequestKind::Message, Answer::Error(Error::NoDisplay));
+
+        let res = FileDialog::new()
+            .set_directory("/home")
+            .set_file_name("out.txt")
+            .with_backend(backend.clone())
+            .save_file();
+        assert_eq!(res, None);
+
+        let res = futures::executor::block_on(
+            AsyncFileDialog::new()
+                .set_title("Import")
+                .with_backend(backend.clone())
+                .pick_files(),
+        );
+        assert_eq!(res.map(|files| files.len()), Some(2));
+
+        let res = MessageDialog::new()
+            .set_buttons(MessageButtons::YesNo)
+            .with_backend(backend.clone())
+            .try_show();
+        assert!(matches!(res, Err(Error::NoDisplay)));
+
+        let requests = backend.finish();
+        let save = requests[0].file_dialog().unwrap();
+        assert_eq!(save.starting_directory(), Some("/home".as_ref()));
+        assert_eq!(save.file_name(), Some("out.txt".as_ref()));
+        assert_eq!(requests[1].kind(), RequestKind::PickFiles);
+        assert_eq!(requests[1].file_dialog().unwrap().title(), Some("Import"));
+        assert!(matches!(
+            requests[2].message_dialog().unwrap().buttons(),
+            MessageButtons::YesNo
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected pick_folder")]
+    fn unexpected_request() {
+        let backend = ScriptedBackend::new();
+        backend.expect(RequestKind::PickFile, Answer::Cancel);
+
+        FileDialog::new().with_backend(backend).pick_folder();
+    }
+}